@@ -1,21 +1,36 @@
 use crate::blink::{
+    archive_view::ArchiveView,
     bookmarks::Bookmarks,
     config::config::Config,
+    dir_sizes::DirSizes,
     entries::{FileEntry, get_entries},
+    filter::NameMatcher,
     logging::{Log, LogManager},
-    operations::OperationManager,
-    states::{main_state::MainState, state_trait::State},
+    operations::{
+        ConflictResolution, OperationManager, PasteItem, PasteMode,
+        archive::{self, ArchiveFormat},
+        dry_run::PlannedJob,
+        permissions_operation::PermissionChange,
+        progress::Workload,
+        transfer,
+    },
+    sorting::{SortKey, SortOrder, SortSettings},
+    states::{dry_run_state::DryRunState, main_state::MainState, state_trait::State},
     thread_pool::ThreadPool,
+    trash_manager::TrashItem,
+    watcher::Watcher,
 };
 use ratatui::{
     crossterm::event::{self, Event, KeyEventKind},
     widgets::{Clear, ListState},
 };
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{
+    collections::{HashMap, HashSet},
     fs,
+    io::{self, Write},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, ExitStatus},
     time::Duration,
 };
 
@@ -23,6 +38,8 @@ use std::{
 pub enum RunningState {
     #[default]
     Running,
+    // Quitting once the running and queued jobs are done
+    Finishing,
     Done,
 }
 
@@ -42,22 +59,42 @@ impl Default for Preview {
     }
 }
 
+// Filled in by the job listing an archive
+type ArchiveListing = Arc<Mutex<Option<io::Result<ArchiveView>>>>;
+
 pub struct App {
     pub running_state: RunningState,
     pub state: Box<dyn State>,
+    // Replaces whatever state handled the last key, for screens opened from inside App
+    pending_state: Option<Box<dyn State>>,
     pub operation_manager: OperationManager,
     pub log_manager: LogManager,
     thread_pool: ThreadPool,
+    pub dir_sizes: DirSizes,
+    watcher: Watcher,
     pub list_state: ListState,
     pub cwd: PathBuf,
     pub yanked_entry_paths: Option<Vec<PathBuf>>,
     pub is_cut: bool,
+    // The archive the yanked paths are members of, pasting them unpacks them
+    pub yanked_archive: Option<PathBuf>,
+    // Set while browsing inside an archive, cwd is then a path below the archive
+    pub archive: Option<ArchiveView>,
+    // An archive being listed in the background, it's opened once the listing is done
+    opening_archive: Option<(PathBuf, ArchiveListing)>,
     pub parent_dir_entries: Vec<FileEntry>,
     pub cwd_entries: Vec<FileEntry>,
     pub visual_mode: bool,
     pub visual_anchor: Option<usize>,
     pub visual_selection: Vec<usize>,
+    // Pastes, deletes and renames show what they'd do and wait for confirmation first
+    pub dry_run: bool,
     pub bookmarks: Bookmarks,
+    pub sort_settings: SortSettings,
+    // The last search, n and N jump between its matches
+    pub search: Option<NameMatcher>,
+    // Directories that only list the entries matching a pattern, until it's cleared
+    filters: HashMap<PathBuf, NameMatcher>,
     pub config: Config,
 }
 
@@ -65,25 +102,45 @@ impl App {
     pub fn new(path: PathBuf) -> color_eyre::Result<App> {
         let config = Config::load()?;
         let bookmarks = Bookmarks::load()?;
+        let (sort_settings, sort_warning) = SortSettings::load();
         let mut app = App {
             running_state: RunningState::Running,
             state: Box::new(MainState),
+            pending_state: None,
             list_state: ListState::default().with_selected(Some(0)),
             cwd: path.clone(),
             yanked_entry_paths: None,
             is_cut: false,
+            yanked_archive: None,
+            archive: None,
+            opening_archive: None,
             parent_dir_entries: Vec::new(),
             cwd_entries: Vec::new(),
             visual_mode: false,
             visual_anchor: None,
             visual_selection: Vec::new(),
-            operation_manager: OperationManager::new(50)?,
+            dry_run: false,
+            operation_manager: OperationManager::new(&config.behavior)?,
             log_manager: LogManager::new(),
             thread_pool: ThreadPool::new(1, 1024),
+            // Walking big trees takes a while, so sizes get their own worker and previews don't
+            // wait behind them
+            dir_sizes: DirSizes::new(ThreadPool::new(1, 64)),
+            watcher: Watcher::new(),
             bookmarks,
+            sort_settings,
+            search: None,
+            filters: HashMap::new(),
             config,
         };
+        if let Some(message) = app.operation_manager.take_journal_warning() {
+            app.log_manager.add_log(Log::Warning { message });
+        }
+        if let Some(message) = sort_warning {
+            app.log_manager.add_log(Log::Warning { message });
+        }
         app.update_all_entries();
+        app.watch_dirs();
         Ok(app)
     }
 
@@ -91,6 +148,16 @@ impl App {
         let mut terminal = ratatui::init();
 
         while self.running_state != RunningState::Done {
+            self.poll_jobs();
+            self.poll_archive();
+            self.poll_dir_sizes();
+            if self.running_state == RunningState::Finishing
+                && self.operation_manager.job_count() == 0
+            {
+                self.running_state = RunningState::Done;
+                break;
+            }
+            self.poll_watcher();
             terminal.draw(|frame| {
                 frame.render_widget(Clear, frame.area());
                 self.state.render(self, frame)
@@ -100,6 +167,9 @@ impl App {
                     if key.kind == KeyEventKind::Press {
                         let old_state = std::mem::replace(&mut self.state, Box::new(MainState));
                         self.state = old_state.handle_input(key.code, self);
+                        if let Some(state) = self.pending_state.take() {
+                            self.state = state;
+                        }
                     }
                 }
             }
@@ -109,35 +179,173 @@ impl App {
     }
 
     fn update_cwd(&mut self, path: PathBuf) {
+        if self
+            .archive
+            .as_ref()
+            .is_some_and(|view| !view.contains(&path))
+        {
+            self.archive = None;
+        }
         self.cwd = path;
         self.list_state.select(Some(0));
         self.update_all_entries();
+        self.watch_dirs();
+    }
+
+    // Archives are read once when they're opened, so there's nothing to watch inside them
+    fn watch_dirs(&mut self) {
+        if self.archive.is_some() {
+            self.watcher.watch(&[]);
+            return;
+        }
+        let mut dirs = vec![self.cwd.as_path()];
+        dirs.extend(self.cwd.parent());
+        self.watcher.watch(&dirs);
+    }
+
+    fn poll_watcher(&mut self) {
+        if let Some(changed) = self.watcher.poll() {
+            self.refresh_changed(changed.into_iter().collect());
+        }
+    }
+
+    // Reloads the listings after something outside blink changed them. A change to the cwd
+    // itself could be anything, so then every preview loads again
+    fn refresh_changed(&mut self, changed: HashSet<PathBuf>) {
+        if self.archive.is_some() {
+            return;
+        }
+
+        let everything = changed.contains(&self.cwd);
+        self.update_parent_dir_entries();
+        self.reload_cwd_entries(|path| everything || changed.contains(path));
+        self.request_dir_sizes();
+    }
+
+    // Lists the cwd again with the cursor staying on the same entry. Entries that were already
+    // listed keep their previews unless stale says otherwise
+    fn reload_cwd_entries(&mut self, stale: impl Fn(&Path) -> bool) {
+        let selected = self.selected_entry_path();
+        let previews: HashMap<PathBuf, Arc<Mutex<Preview>>> = self
+            .cwd_entries
+            .iter()
+            .map(|entry| (entry.path.clone(), Arc::clone(&entry.preview)))
+            .collect();
+        self.update_cwd_entries();
+
+        let mut unloaded = Vec::new();
+        for entry in self.cwd_entries.iter_mut() {
+            match previews.get(&entry.path) {
+                Some(preview) if !stale(&entry.path) => {
+                    entry.preview = Arc::clone(preview);
+                }
+                _ => unloaded.push(entry.clone()),
+            }
+        }
+        if self.archive.is_some() {
+            self.preload_previews();
+        } else {
+            self.load_previews(unloaded);
+        }
+        self.select_entry_path(selected);
+    }
+
+    fn selected_entry_path(&self) -> Option<PathBuf> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.cwd_entries.get(i))
+            .map(|entry| entry.path.clone())
+    }
+
+    // Puts the cursor back on an entry after the listing was rebuilt, if it's still there
+    fn select_entry_path(&mut self, path: Option<PathBuf>) {
+        if let Some(i) =
+            path.and_then(|path| self.cwd_entries.iter().position(|entry| entry.path == path))
+        {
+            self.list_state.select(Some(i));
+        }
     }
 
     fn update_cwd_entries(&mut self) {
-        self.cwd_entries =
-            get_entries(self.config.ui.show_hidden, self.cwd.as_path()).unwrap_or_default();
+        let order = self.sort_settings.order_for(&self.cwd);
+        self.cwd_entries = match &self.archive {
+            Some(view) => view.entries(self.config.ui.show_hidden, &self.cwd, &order),
+            None => get_entries(self.config.ui.show_hidden, self.cwd.as_path(), &order)
+                .unwrap_or_default(),
+        };
+        if let Some(filter) = self.filters.get(&self.cwd) {
+            self.cwd_entries
+                .retain(|entry| filter.is_match(&entry.name));
+        }
+        self.sort_by_dir_sizes();
+
+        // Entries can disappear underneath the cursor when a background job finishes
+        if let Some(i) = self.list_state.selected()
+            && i >= self.cwd_entries.len()
+        {
+            self.list_state
+                .select(Some(self.cwd_entries.len().saturating_sub(1)));
+        }
+    }
+
+    // Directories are listed with the size of the inode, a sort by size uses what they hold once
+    // that's known
+    fn sort_by_dir_sizes(&mut self) {
+        let order = self.sort_settings.order_for(&self.cwd);
+        if order.key != SortKey::Size || self.archive.is_some() {
+            return;
+        }
+        let mut entries = std::mem::take(&mut self.cwd_entries);
+        order.apply_with_sizes(&mut entries, |entry| self.entry_size(entry));
+        self.cwd_entries = entries;
+    }
+
+    // Sorts the listing again as directory sizes come in. The visual selection is kept by
+    // position, so that waits until visual mode ends
+    fn poll_dir_sizes(&mut self) {
+        if self.visual_mode || !self.dir_sizes.take_updated() {
+            return;
+        }
+        let selected = self.selected_entry_path();
+        self.sort_by_dir_sizes();
+        self.select_entry_path(selected);
     }
 
     fn update_parent_dir_entries(&mut self) {
-        if let Some(parent) = self.cwd.parent() {
+        if let Some(view) = &self.archive
+            && self.cwd != view.path
+            && let Some(parent) = self.cwd.parent()
+        {
+            let order = self.sort_settings.order_for(parent);
+            self.parent_dir_entries = view.entries(self.config.ui.show_hidden, parent, &order);
+        } else if let Some(parent) = self.cwd.parent() {
+            let order = self.sort_settings.order_for(parent);
             self.parent_dir_entries =
-                get_entries(self.config.ui.show_hidden, parent).unwrap_or_default();
+                get_entries(self.config.ui.show_hidden, parent, &order).unwrap_or_default();
         } else {
             self.parent_dir_entries.clear();
         }
     }
 
     fn preload_previews(&mut self) {
-        let entries = self.cwd_entries.clone();
-        for (_, entry) in entries.iter().enumerate() {
+        if let Some(view) = &self.archive {
+            self.preload_archive_previews(view.path.clone());
+            return;
+        }
+
+        self.load_previews(self.cwd_entries.clone());
+    }
+
+    fn load_previews(&mut self, entries: Vec<FileEntry>) {
+        for entry in entries {
             let path = entry.path.clone();
             let is_dir = entry.is_dir;
             let preview = Arc::clone(&entry.preview);
+            let order = self.sort_settings.order_for(&path);
 
             let status = self.thread_pool.try_execute(move || {
                 let new_preview = if is_dir {
-                    load_directory_preview(true, &path)
+                    load_directory_preview(true, &path, &order)
                 } else {
                     load_file_preview(&path)
                 };
@@ -157,10 +365,113 @@ impl App {
         }
     }
 
+    // Directory listings come from the archive's index, file contents have to be read out of
+    // the archive so those are loaded in the background, all in one go
+    fn preload_archive_previews(&mut self, archive_path: PathBuf) {
+        let Some(view) = &self.archive else {
+            return;
+        };
+
+        let mut files = Vec::new();
+        for entry in self.cwd_entries.clone() {
+            if entry.is_dir {
+                let entries = view.entries(
+                    true,
+                    &entry.path,
+                    &self.sort_settings.order_for(&entry.path),
+                );
+                if let Ok(mut p) = entry.preview.lock() {
+                    *p = Preview::Directory { entries };
+                }
+            } else if let Some(member) = view.member_path(&entry.path).map(Path::to_path_buf) {
+                files.push((member, entry));
+            }
+        }
+        if files.is_empty() {
+            return;
+        }
+
+        let status = self.thread_pool.try_execute(move || {
+            // Leaving the directory before the job got its turn drops the previews
+            if files
+                .iter()
+                .all(|(_, entry)| Arc::strong_count(&entry.preview) == 1)
+            {
+                return;
+            }
+            let members = files.iter().map(|(member, _)| member.clone()).collect();
+            let mut contents = archive::read_members(&archive_path, &members, PREVIEW_LIMIT as u64)
+                .unwrap_or_default();
+            for (member, entry) in files {
+                let new_preview = match contents.remove(&member) {
+                    Some(bytes) => preview_from_bytes(&bytes, entry.size),
+                    None => Preview::File {
+                        contents: "[Cannot read file]".to_string(),
+                    },
+                };
+                if let Ok(mut p) = entry.preview.lock() {
+                    *p = new_preview;
+                }
+            }
+        });
+
+        if let Err(e) = status {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to load a preview {}", e),
+            });
+        }
+    }
+
     fn update_all_entries(&mut self) {
         self.update_cwd_entries();
         self.update_parent_dir_entries();
         self.preload_previews();
+        self.request_dir_sizes();
+    }
+
+    fn request_dir_sizes(&mut self) {
+        if self.archive.is_some() {
+            self.dir_sizes.cancel();
+            return;
+        }
+        if let Err(message) = self.dir_sizes.request(&self.cwd, &self.cwd_entries) {
+            self.log_manager.add_log(Log::Error { message });
+        }
+    }
+
+    // What an entry takes up on disk, None while a directory's size is still being worked out
+    pub fn entry_size(&self, entry: &FileEntry) -> Option<u64> {
+        if entry.is_dir && self.archive.is_none() {
+            self.dir_sizes.size_of(entry)
+        } else {
+            Some(entry.size)
+        }
+    }
+
+    // Adds up what the paths take in the background, the result stays None until it's done.
+    // Once the caller lets go of it the remaining paths are skipped
+    pub fn measure_paths(&mut self, paths: Vec<PathBuf>) -> Arc<Mutex<Option<Workload>>> {
+        let result = Arc::new(Mutex::new(None));
+        let shared = Arc::clone(&result);
+        let status = self.thread_pool.try_execute(move || {
+            let mut total = Workload::default();
+            for path in paths {
+                if Arc::strong_count(&shared) == 1 {
+                    return;
+                }
+                total = total + transfer::measure(&path);
+            }
+            if let Ok(mut result) = shared.lock() {
+                *result = Some(total);
+            }
+        });
+
+        if let Err(e) = status {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to measure {}", e),
+            });
+        }
+        result
     }
 
     pub fn toggle_visual_mode(&mut self) {
@@ -186,6 +497,7 @@ impl App {
     }
 
     pub fn yank_current_selection(&mut self, cut: bool) {
+        self.yanked_archive = self.archive.as_ref().map(|view| view.path.clone());
         if self.visual_mode {
             let paths: Vec<PathBuf> = self
                 .visual_selection
@@ -255,11 +567,78 @@ impl App {
                 if entry.is_dir {
                     self.list_state.select(Some(0));
                     self.update_cwd(entry.path.clone());
+                } else if self.archive.is_none() && ArchiveFormat::from_name(&entry.name).is_some()
+                {
+                    let path = entry.path.clone();
+                    self.open_archive(path);
+                }
+            }
+        }
+    }
+
+    // Lists the archive in the background, a big compressed tar takes a while to read through.
+    // It's browsed like a directory once poll_archive sees the listing is done
+    fn open_archive(&mut self, path: PathBuf) {
+        if self
+            .opening_archive
+            .as_ref()
+            .is_some_and(|(opening, _)| *opening == path)
+        {
+            return;
+        }
+
+        let listing = Arc::new(Mutex::new(None));
+        let status = self.thread_pool.try_execute({
+            let path = path.clone();
+            let listing = Arc::clone(&listing);
+            move || {
+                let view = ArchiveView::open(&path);
+                if let Ok(mut listing) = listing.lock() {
+                    *listing = Some(view);
                 }
             }
+        });
+
+        match status {
+            Ok(_) => self.opening_archive = Some((path, listing)),
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed to open archive {}: {}", path.display(), e),
+            }),
+        }
+    }
+
+    fn poll_archive(&mut self) {
+        let Some((path, listing)) = &self.opening_archive else {
+            return;
+        };
+        let Some(view) = listing.lock().ok().and_then(|mut listing| listing.take()) else {
+            return;
+        };
+        let path = path.clone();
+        self.opening_archive = None;
+
+        // Moving elsewhere while the archive was being listed means it's no longer wanted
+        if self.archive.is_some() || path.parent() != Some(self.cwd.as_path()) {
+            return;
+        }
+        match view {
+            Ok(view) => {
+                self.archive = Some(view);
+                self.list_state.select(Some(0));
+                self.update_cwd(path);
+            }
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed to open archive {}: {}", path.display(), e),
+            }),
         }
     }
 
+    pub fn opening_archive(&self) -> Option<&Path> {
+        self.opening_archive
+            .as_ref()
+            .map(|(path, _)| path.as_path())
+    }
+
     pub fn go_up_one_directory_level(&mut self) {
         if let Some(parent) = self.cwd.parent() {
             self.update_cwd(parent.to_path_buf());
@@ -297,10 +676,66 @@ impl App {
         self.list_state.select(Some(0));
     }
 
+    // A filter that matches nothing leaves the listing empty
     pub fn jump_to_bottom(&mut self) {
+        if self.cwd_entries.is_empty() {
+            return;
+        }
         self.list_state.select(Some(self.cwd_entries.len() - 1));
     }
 
+    // The first entry from start on that matches the search, wrapping around the listing
+    pub fn find_match(&self, start: usize, forward: bool) -> Option<usize> {
+        let search = self.search.as_ref()?;
+        let len = self.cwd_entries.len();
+        (0..len)
+            .map(|step| {
+                if forward {
+                    (start + step) % len
+                } else {
+                    (start + len - step) % len
+                }
+            })
+            .find(|&i| search.is_match(&self.cwd_entries[i].name))
+    }
+
+    pub fn search_next(&mut self, forward: bool) {
+        let Some(search) = &self.search else {
+            self.log_manager.add_log(Log::Info {
+                message: "Nothing searched for yet".to_string(),
+            });
+            return;
+        };
+        let pattern = search.pattern.clone();
+        let len = self.cwd_entries.len();
+        let current = self.list_state.selected().unwrap_or(0);
+        let start = if forward {
+            current + 1
+        } else {
+            current + len.max(1) - 1
+        };
+
+        match self.find_match(start % len.max(1), forward) {
+            Some(i) => self.list_state.select(Some(i)),
+            None => self.log_manager.add_log(Log::Warning {
+                message: format!("Pattern not found: {}", pattern),
+            }),
+        }
+    }
+
+    pub fn filter(&self) -> Option<&NameMatcher> {
+        self.filters.get(&self.cwd)
+    }
+
+    pub fn set_filter(&mut self, filter: Option<NameMatcher>) {
+        match filter {
+            Some(filter) => self.filters.insert(self.cwd.clone(), filter),
+            None => self.filters.remove(&self.cwd),
+        };
+        self.reload_cwd_entries(|_| false);
+        self.request_dir_sizes();
+    }
+
     pub fn toggle_hidden_file_visibility(&mut self) {
         self.config.ui.show_hidden = !self.config.ui.show_hidden;
         self.update_all_entries();
@@ -340,6 +775,84 @@ impl App {
         Ok(())
     }
 
+    // Writes the names of the visual selection, or the whole directory, to a temp file and
+    // turns the lines edited in $EDITOR into renames. Line n is the new name of entry n
+    pub fn bulk_rename(&mut self) -> color_eyre::Result<()> {
+        let paths = if self.visual_mode {
+            self.selected_paths()
+        } else {
+            self.cwd_entries
+                .iter()
+                .map(|entry| entry.path.clone())
+                .collect()
+        };
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(editor) = std::env::var("EDITOR").or_else(|_| std::env::var("VISUAL")) else {
+            self.log_manager.add_log(Log::Error {
+                message: "Set $EDITOR or $VISUAL to bulk rename".to_string(),
+            });
+            return Ok(());
+        };
+
+        let names: Option<Vec<&str>> = paths
+            .iter()
+            .map(|path| path.file_name().and_then(|name| name.to_str()))
+            .collect();
+        let Some(names) = names.filter(|names| names.iter().all(|name| !name.contains('\n')))
+        else {
+            self.log_manager.add_log(Log::Error {
+                message:
+                    "Bulk rename can't handle names that aren't valid UTF-8 or contain newlines"
+                        .to_string(),
+            });
+            return Ok(());
+        };
+
+        let (list_path, mut list_file) = create_private_temp_file("blink-rename", "txt")?;
+        let written = list_file.write_all((names.join("\n") + "\n").as_bytes());
+        drop(list_file);
+        let edited = written
+            .map_err(color_eyre::Report::from)
+            .and_then(|_| self.open_with_terminal(&editor, &list_path))
+            .and_then(|status| {
+                if !status.success() {
+                    return Err(color_eyre::eyre::eyre!("{} exited with {}", editor, status));
+                }
+                Ok(fs::read_to_string(&list_path)?)
+            });
+        _ = fs::remove_file(&list_path);
+        let edited = edited?;
+
+        let new_names: Vec<&str> = edited.lines().collect();
+        if new_names.len() != paths.len() {
+            self.log_manager.add_log(Log::Error {
+                message: format!(
+                    "Bulk rename cancelled: expected {} lines but got {}",
+                    paths.len(),
+                    new_names.len()
+                ),
+            });
+            return Ok(());
+        }
+
+        let mut renames = Vec::new();
+        for (path, name) in paths.iter().zip(new_names) {
+            if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+                self.log_manager.add_log(Log::Error {
+                    message: format!("Bulk rename cancelled: {:?} isn't a valid name", name),
+                });
+                return Ok(());
+            }
+            renames.push((path.clone(), self.cwd.join(name)));
+        }
+
+        self.rename_paths(renames);
+        Ok(())
+    }
+
     // Drops into the terminal editor from blink and returns to blink once the editor closes
     // checks if given path is a directory which would be list_state.selected()
     // if its a directory, set the terminal app current dir to it, otherwise use cwd
@@ -347,9 +860,9 @@ impl App {
         &mut self,
         editor: &str,
         path: &std::path::Path,
-    ) -> color_eyre::Result<()> {
+    ) -> color_eyre::Result<ExitStatus> {
         ratatui::restore();
-        _ = Command::new(editor)
+        let status = Command::new(editor)
             .arg(path)
             .current_dir(if path.is_dir() { path } else { &self.cwd })
             .status();
@@ -363,7 +876,29 @@ impl App {
             self.state.render(self, frame);
         })?;
 
-        Ok(())
+        Ok(status?)
+    }
+}
+
+// A temp file only the current user can read, under a name nobody could have guessed and
+// planted a symlink at. create_new refuses to open anything that's already there
+fn create_private_temp_file(prefix: &str, extension: &str) -> io::Result<(PathBuf, fs::File)> {
+    use std::hash::{BuildHasher, RandomState};
+
+    loop {
+        let random = RandomState::new().hash_one(std::process::id());
+        let path = std::env::temp_dir().join(format!("{}-{:016x}.{}", prefix, random, extension));
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
     }
 }
 
@@ -383,6 +918,16 @@ impl App {
         self.update_all_entries();
     }
 
+    pub fn redo_last_operation(&mut self) {
+        if let Err(e) = self.operation_manager.redo() {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to redo last operation: {}", e),
+            });
+        }
+
+        self.update_all_entries();
+    }
+
     pub fn create_file(&mut self, name: &str) {
         let status = self.operation_manager.create_file(self.cwd.join(name));
         match status {
@@ -426,56 +971,336 @@ impl App {
         }
     }
 
-    pub fn delete_current_selection(&mut self) {
+    // The visual selection, or the entry under the cursor outside of visual mode
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
         if self.visual_mode && !self.visual_selection.is_empty() {
-            for &idx in &self.visual_selection {
-                if let Some(entry) = self.cwd_entries.get(idx) {
-                    _ = self.operation_manager.delete_file(entry.path.clone());
+            self.visual_selection
+                .iter()
+                .filter_map(|&idx| self.cwd_entries.get(idx))
+                .map(|entry| entry.path.clone())
+                .collect()
+        } else {
+            self.list_state
+                .selected()
+                .and_then(|i| self.cwd_entries.get(i))
+                .map(|entry| vec![entry.path.clone()])
+                .unwrap_or_default()
+        }
+    }
+
+    pub fn change_permissions(
+        &mut self,
+        paths: Vec<PathBuf>,
+        change: PermissionChange,
+        recursive: bool,
+    ) {
+        self.operation_manager
+            .change_permissions(paths, change, recursive);
+    }
+
+    pub fn create_archive(&mut self, sources: Vec<PathBuf>, name: &str, format: ArchiveFormat) {
+        let archive_path = self.cwd.join(name);
+        if fs::symlink_metadata(&archive_path).is_ok() {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to create archive {}: Path already exists", name),
+            });
+            return;
+        }
+        self.operation_manager
+            .create_archive(sources, archive_path, format);
+    }
+
+    pub fn extract_archive(&mut self, archive_path: PathBuf, destination: PathBuf) {
+        self.operation_manager
+            .extract_archive(archive_path, Vec::new(), destination);
+    }
+
+    // Runs several renames as one undoable batch
+    pub fn rename_paths(&mut self, renames: Vec<(PathBuf, PathBuf)>) {
+        match self.operation_manager.plan_renames(renames) {
+            Ok(job) => self.submit_job(job, false),
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed to rename: {}", e),
+            }),
+        }
+        self.update_all_entries();
+    }
+
+    pub fn delete_current_selection(&mut self) {
+        let paths = self.selected_paths();
+        if !paths.is_empty() {
+            let job = self.operation_manager.plan_delete(paths);
+            self.submit_job(job, false);
+        }
+    }
+
+    pub fn permanently_delete_current_selection(&mut self) {
+        let paths = self.selected_paths();
+        if !paths.is_empty() {
+            let job = self.operation_manager.plan_purge(paths);
+            self.submit_job(job, false);
+        }
+    }
+
+    pub fn sort_order(&self) -> SortOrder {
+        self.sort_settings.order_for(&self.cwd)
+    }
+
+    // Applies a new sort to the cwd and remembers it for next time, keeping the cursor on the
+    // same entry
+    pub fn set_sort_order(&mut self, order: SortOrder) {
+        let selected = self.selected_entry_path();
+        self.sort_settings.set(self.cwd.clone(), order);
+        if let Err(e) = self.sort_settings.save() {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to save the sort order: {}", e),
+            });
+        }
+        self.update_all_entries();
+        self.select_entry_path(selected);
+    }
+
+    pub fn toggle_dry_run(&mut self) {
+        self.dry_run = !self.dry_run;
+        self.log_manager.add_log(Log::Info {
+            message: if self.dry_run {
+                "Dry run on, changes are previewed before they run".to_string()
+            } else {
+                "Dry run off".to_string()
+            },
+        });
+    }
+
+    // Runs the job, or opens its preview first while dry run is on
+    fn submit_job(&mut self, job: PlannedJob, clears_yank: bool) {
+        if job.is_empty() {
+            if clears_yank {
+                self.clear_yank();
+            }
+            return;
+        }
+        if self.dry_run {
+            self.pending_state = Some(Box::new(DryRunState::new(job, clears_yank)));
+        } else {
+            self.run_job(job, clears_yank);
+        }
+    }
+
+    fn clear_yank(&mut self) {
+        self.yanked_entry_paths = None;
+        self.is_cut = false;
+    }
+
+    // A delete only moves the cursor once it actually runs, not when dry run opened its preview
+    pub fn run_job(&mut self, job: PlannedJob, clears_yank: bool) {
+        let description = job.description.clone();
+        let background = job.background;
+        let removes = job.only_removes();
+        match self.operation_manager.run(job) {
+            Ok(_) => {
+                if clears_yank {
+                    self.clear_yank();
+                }
+                if removes {
+                    self.move_cursor_after_delete();
+                }
+                // Background jobs log once they're done
+                if !background {
+                    self.log_manager.add_log(Log::Info {
+                        message: format!("Finished: {}", description),
+                    });
                 }
             }
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed: {}: {}", description, e),
+            }),
+        }
+        self.update_all_entries();
+    }
+
+    fn move_cursor_after_delete(&mut self) {
+        if self.visual_mode || self.list_state.selected().is_some_and(|i| i != 0) {
             self.move_cursor_up();
-            self.update_all_entries();
+        }
+    }
+
+    // Works out where each yanked path lands in the cwd. Copying or linking a path onto itself
+    // keeps both like it always has, moving it onto itself does nothing
+    pub fn plan_paste(&self, mode: PasteMode) -> Vec<PasteItem> {
+        let Some(sources) = &self.yanked_entry_paths else {
+            return Vec::new();
+        };
+
+        sources
+            .iter()
+            .filter_map(|source| {
+                let target = self.cwd.join(source.file_name()?);
+                let resolution = if &target == source {
+                    Some(if mode == PasteMode::Move {
+                        ConflictResolution::Skip
+                    } else {
+                        ConflictResolution::KeepBoth
+                    })
+                } else {
+                    None
+                };
+                Some(PasteItem {
+                    source: source.clone(),
+                    target,
+                    resolution,
+                })
+            })
+            .collect()
+    }
+
+    // Copies and moves use up the yank, links leave it so the same paths can be linked again
+    // Members yanked inside an archive get unpacked into the cwd instead of copied
+    pub fn paste_archive_members(&mut self) {
+        let (Some(archive_path), Some(paths)) =
+            (self.yanked_archive.take(), self.yanked_entry_paths.take())
+        else {
+            return;
+        };
+
+        let members = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&archive_path).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        self.operation_manager
+            .extract_archive(archive_path, members, self.cwd.clone());
+    }
+
+    // Links can be pasted again, so only copies and moves use up the yank
+    pub fn paste_yanked_path(&mut self, items: Vec<PasteItem>, mode: PasteMode) {
+        let job = self.operation_manager.plan_paste(items, mode);
+        self.submit_job(job, !matches!(mode, PasteMode::Link(_)));
+    }
+
+    pub fn yank_paste_mode(&self) -> PasteMode {
+        if self.is_cut {
+            PasteMode::Move
         } else {
-            if let Some(i) = self.list_state.selected() {
-                if let Some(entry) = self.cwd_entries.get(i) {
-                    _ = self.operation_manager.delete_file(entry.path.clone());
+            PasteMode::Copy
+        }
+    }
 
-                    if i != 0 {
-                        self.move_cursor_up();
-                    }
-                    self.update_all_entries();
+    // Logs the background jobs that finished since the last frame and refreshes the listing
+    fn poll_jobs(&mut self) {
+        let finished = self.operation_manager.poll_jobs();
+        if finished.is_empty() {
+            return;
+        }
+
+        for job in finished {
+            let log = match job.result {
+                Ok(_) => Log::Info {
+                    message: format!("Finished: {}", job.description),
+                },
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => Log::Warning {
+                    message: format!("Cancelled: {}", job.description),
+                },
+                Err(e) => Log::Error {
+                    message: format!("Failed: {}: {}", job.description, e),
+                },
+            };
+            self.log_manager.add_log(log);
+        }
+        self.update_all_entries();
+    }
+
+    pub fn cancel_jobs(&mut self) {
+        let cancelled = self.operation_manager.cancel_jobs();
+        if cancelled > 0 {
+            self.log_manager.add_log(Log::Warning {
+                message: format!("Cancelling {} job(s)", cancelled),
+            });
+        }
+    }
+}
+
+// Trash functions
+impl App {
+    // Puts a trashed entry back where it came from, or under the given path. A path naming
+    // an existing directory restores into it with the original name
+    pub fn restore_trash_item(&mut self, item: &TrashItem, destination: Option<&str>) {
+        let target = match destination {
+            Some(input) => {
+                let path = self.cwd.join(input.trim());
+                match item.original_path.file_name() {
+                    Some(name) if path.is_dir() => path.join(name),
+                    _ => path,
                 }
             }
+            None => item.original_path.clone(),
+        };
+
+        match self.operation_manager.restore_from_trash(item, &target) {
+            Ok(_) => self.log_manager.add_log(Log::Info {
+                message: format!("Restored {}", target.display()),
+            }),
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed to restore {}: {}", target.display(), e),
+            }),
         }
+        self.update_all_entries();
     }
 
-    pub fn paste_yanked_path(&mut self) {
-        if let Some(sources) = &self.yanked_entry_paths.clone() {
-            if self.is_cut {
-                for source in sources {
-                    if let Some(filename) = source.file_name() {
-                        _ = self
-                            .operation_manager
-                            .rename_file(source.clone(), self.cwd.join(filename).clone())
-                    }
+    // Measures the items one after another in a single job. Once the browser is closed nothing
+    // else holds on to their sizes and the rest are skipped
+    pub fn measure_trash_items(&mut self, items: Vec<TrashItem>) {
+        if items.is_empty() {
+            return;
+        }
+        let status = self.thread_pool.try_execute(move || {
+            for item in items {
+                if Arc::strong_count(&item.size) == 1 {
+                    continue;
                 }
-            } else {
-                for source in sources {
-                    _ = self
-                        .operation_manager
-                        .copy_file(source.clone(), self.cwd.clone())
+                let bytes = transfer::measure(&item.trash_path).bytes;
+                if let Ok(mut size) = item.size.lock() {
+                    *size = Some(bytes);
                 }
             }
-            self.yanked_entry_paths = None;
-            self.is_cut = false;
-            self.update_all_entries();
+        });
+
+        if let Err(e) = status {
+            self.log_manager.add_log(Log::Error {
+                message: format!("Failed to measure the trash {}", e),
+            });
+        }
+    }
+
+    pub fn purge_trash_item(&mut self, item: &TrashItem) {
+        match self.operation_manager.purge_from_trash(item) {
+            Ok(_) => self.log_manager.add_log(Log::Info {
+                message: format!("Permanently deleted {}", item.original_path.display()),
+            }),
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!(
+                    "Failed to delete {} from the trash: {}",
+                    item.original_path.display(),
+                    e
+                ),
+            }),
+        }
+    }
+
+    pub fn empty_trash(&mut self) {
+        match self.operation_manager.empty_trash() {
+            Ok(_) => self.log_manager.add_log(Log::Info {
+                message: "Emptied the trash".to_string(),
+            }),
+            Err(e) => self.log_manager.add_log(Log::Error {
+                message: format!("Failed to empty the trash: {}", e),
+            }),
         }
     }
 }
 
-fn load_directory_preview(show_hidden: bool, path: &Path) -> Preview {
+fn load_directory_preview(show_hidden: bool, path: &Path, order: &SortOrder) -> Preview {
     Preview::Directory {
-        entries: get_entries(show_hidden, path).unwrap_or_default(),
+        entries: get_entries(show_hidden, path, order).unwrap_or_default(),
     }
 }
 
@@ -493,25 +1318,25 @@ fn load_file_preview(path: &Path) -> Preview {
     }
 
     match fs::read(path) {
-        Ok(bytes) => {
-            let check_len = bytes.len().min(8192);
-            if bytes[..check_len].contains(&0) {
-                Preview::Binary {
-                    info: format!("Binary file ({} bytes)", bytes.len()),
-                }
-            } else {
-                let preview_bytes = if bytes.len() > 1_000_000 {
-                    &bytes[..1_000_000]
-                } else {
-                    &bytes
-                };
-                Preview::File {
-                    contents: String::from_utf8_lossy(preview_bytes).to_string(),
-                }
-            }
-        }
+        Ok(bytes) => preview_from_bytes(&bytes, bytes.len() as u64),
         Err(_) => Preview::File {
             contents: "[Cannot read file]".to_string(),
         },
     }
 }
+
+const PREVIEW_LIMIT: usize = 1_000_000;
+
+fn preview_from_bytes(bytes: &[u8], size: u64) -> Preview {
+    let check_len = bytes.len().min(8192);
+    if bytes[..check_len].contains(&0) {
+        Preview::Binary {
+            info: format!("Binary file ({} bytes)", size),
+        }
+    } else {
+        let preview_bytes = &bytes[..bytes.len().min(PREVIEW_LIMIT)];
+        Preview::File {
+            contents: String::from_utf8_lossy(preview_bytes).to_string(),
+        }
+    }
+}
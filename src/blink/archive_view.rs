@@ -0,0 +1,72 @@
+use crate::blink::{
+    app::Preview,
+    entries::FileEntry,
+    operations::archive::{self, ArchiveMember},
+    sorting::SortOrder,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+// An archive opened as a read-only directory tree. Members show up below the archive's own
+// path, so /tmp/photos.zip/2024/a.jpg is the member 2024/a.jpg
+pub struct ArchiveView {
+    pub path: PathBuf,
+    members: Vec<ArchiveMember>,
+}
+
+impl ArchiveView {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            members: archive::list(path)?,
+        })
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.path)
+    }
+
+    // The member a path inside the view stands for, the archive itself is the empty path
+    pub fn member_path<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        path.strip_prefix(&self.path).ok()
+    }
+
+    pub fn entries(&self, show_hidden: bool, dir: &Path, order: &SortOrder) -> Vec<FileEntry> {
+        let Some(dir) = self.member_path(dir) else {
+            return Vec::new();
+        };
+
+        let mut entries: Vec<FileEntry> = self
+            .members
+            .iter()
+            .filter(|member| member.path.parent() == Some(dir))
+            .filter_map(|member| {
+                let name = member.path.file_name()?.to_string_lossy().into_owned();
+                if !show_hidden && name.starts_with('.') {
+                    return None;
+                }
+                Some(FileEntry {
+                    path: self.path.join(&member.path),
+                    name,
+                    preview: Arc::new(Mutex::new(Preview::default())),
+                    size: member.size,
+                    is_dir: member.is_dir,
+                    modified: None,
+                    created: None,
+                    mode: None,
+                    owner: None,
+                    group: None,
+                    links: None,
+                    symlink_target: None,
+                    broken_link: false,
+                })
+            })
+            .collect();
+
+        order.apply(&mut entries);
+        entries
+    }
+}
@@ -4,12 +4,21 @@ use serde::{Deserialize, Serialize};
 pub struct Behavior {
     #[serde(default = "default_confirm_delete")]
     pub confirm_delete: bool,
+    #[serde(default = "default_max_history")]
+    pub max_history: usize,
+    #[serde(default = "default_follow_symlinks")]
+    pub follow_symlinks: bool,
+    #[serde(default = "default_verify_copies")]
+    pub verify_copies: bool,
 }
 
 impl Default for Behavior {
     fn default() -> Self {
         Self {
             confirm_delete: default_confirm_delete(),
+            max_history: default_max_history(),
+            follow_symlinks: default_follow_symlinks(),
+            verify_copies: default_verify_copies(),
         }
     }
 }
@@ -17,3 +26,15 @@ impl Default for Behavior {
 fn default_confirm_delete() -> bool {
     true
 }
+
+fn default_max_history() -> usize {
+    50
+}
+
+fn default_follow_symlinks() -> bool {
+    false
+}
+
+fn default_verify_copies() -> bool {
+    false
+}
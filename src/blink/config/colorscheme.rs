@@ -56,6 +56,8 @@ pub struct ColorScheme {
     pub prompt_bg: ColorConfig,
     #[serde(default = "default_prompt_border")]
     pub prompt_border: ColorConfig,
+    #[serde(default = "default_search_match")]
+    pub search_match: ColorConfig,
 
     // Log colors
     #[serde(default = "default_log_info")]
@@ -162,6 +164,10 @@ fn default_prompt_border() -> ColorConfig {
     ColorConfig::Named("white".to_string())
 }
 
+fn default_search_match() -> ColorConfig {
+    ColorConfig::Named("yellow".to_string())
+}
+
 // Default functions for logs
 fn default_log_info() -> ColorConfig {
     ColorConfig::Named("cyan".to_string())
@@ -233,6 +239,7 @@ impl Default for ColorScheme {
             status_bar: default_status_bar(),
             prompt_bg: default_prompt_bg(),
             prompt_border: default_prompt_border(),
+            search_match: default_search_match(),
             log_info: default_log_info(),
             log_warning: default_log_warning(),
             log_error: default_log_error(),
@@ -24,10 +24,24 @@ pub struct Keybindings {
     pub cut: Vec<String>,
     #[serde(default = "default_key_paste")]
     pub paste: Vec<String>,
+    #[serde(default = "default_key_paste_link")]
+    pub paste_link: Vec<String>,
     #[serde(default = "default_key_delete")]
     pub delete: Vec<String>,
+    #[serde(default = "default_key_permanent_delete")]
+    pub permanent_delete: Vec<String>,
     #[serde(default = "default_key_rename")]
     pub rename: Vec<String>,
+    #[serde(default = "default_key_bulk_rename")]
+    pub bulk_rename: Vec<String>,
+    #[serde(default = "default_key_pattern_rename")]
+    pub pattern_rename: Vec<String>,
+    #[serde(default = "default_key_permissions")]
+    pub permissions: Vec<String>,
+    #[serde(default = "default_key_archive")]
+    pub archive: Vec<String>,
+    #[serde(default = "default_key_extract")]
+    pub extract: Vec<String>,
     #[serde(default = "default_key_new")]
     pub new_entry: Vec<String>,
 
@@ -50,6 +64,30 @@ pub struct Keybindings {
     // Misc
     #[serde(default = "default_key_undo")]
     pub undo: Vec<String>,
+    #[serde(default = "default_key_redo")]
+    pub redo: Vec<String>,
+    #[serde(default = "default_key_history")]
+    pub history: Vec<String>,
+    #[serde(default = "default_key_cancel_jobs")]
+    pub cancel_jobs: Vec<String>,
+    #[serde(default = "default_key_jobs")]
+    pub jobs: Vec<String>,
+    #[serde(default = "default_key_dry_run")]
+    pub dry_run: Vec<String>,
+    #[serde(default = "default_key_sort")]
+    pub sort: Vec<String>,
+    #[serde(default = "default_key_search")]
+    pub search: Vec<String>,
+    #[serde(default = "default_key_search_next")]
+    pub search_next: Vec<String>,
+    #[serde(default = "default_key_search_prev")]
+    pub search_prev: Vec<String>,
+    #[serde(default = "default_key_filter")]
+    pub filter: Vec<String>,
+    #[serde(default = "default_key_clear_filter")]
+    pub clear_filter: Vec<String>,
+    #[serde(default = "default_key_trash")]
+    pub trash: Vec<String>,
     #[serde(default = "default_key_toggle_hidden")]
     pub toggle_hidden: Vec<String>,
     #[serde(default = "default_key_quit")]
@@ -68,8 +106,15 @@ impl Default for Keybindings {
             yank: default_key_yank(),
             cut: default_key_cut(),
             paste: default_key_paste(),
+            paste_link: default_key_paste_link(),
             delete: default_key_delete(),
+            permanent_delete: default_key_permanent_delete(),
             rename: default_key_rename(),
+            bulk_rename: default_key_bulk_rename(),
+            pattern_rename: default_key_pattern_rename(),
+            permissions: default_key_permissions(),
+            archive: default_key_archive(),
+            extract: default_key_extract(),
             new_entry: default_key_new(),
             open_editor: default_key_open_editor(),
             open_default: default_key_open_default(),
@@ -77,6 +122,18 @@ impl Default for Keybindings {
             bookmark_list: default_key_bookmark_list(),
             bookmark_new: default_key_bookmark_new(),
             undo: default_key_undo(),
+            redo: default_key_redo(),
+            history: default_key_history(),
+            cancel_jobs: default_key_cancel_jobs(),
+            jobs: default_key_jobs(),
+            dry_run: default_key_dry_run(),
+            sort: default_key_sort(),
+            search: default_key_search(),
+            search_next: default_key_search_next(),
+            search_prev: default_key_search_prev(),
+            filter: default_key_filter(),
+            clear_filter: default_key_clear_filter(),
+            trash: default_key_trash(),
             toggle_hidden: default_key_toggle_hidden(),
             quit: default_key_quit(),
         }
@@ -88,6 +145,28 @@ impl Keybindings {
         let key_str = keycode_to_string(key);
         bindings.iter().any(|binding| binding == &key_str)
     }
+
+    // Actions that write to or open files on disk, none of which work inside an archive
+    pub fn edits_files(&self, key: KeyCode) -> bool {
+        [
+            &self.cut,
+            &self.paste,
+            &self.paste_link,
+            &self.delete,
+            &self.permanent_delete,
+            &self.rename,
+            &self.bulk_rename,
+            &self.pattern_rename,
+            &self.permissions,
+            &self.new_entry,
+            &self.archive,
+            &self.extract,
+            &self.open_editor,
+            &self.open_default,
+        ]
+        .iter()
+        .any(|bindings| self.matches(key, bindings))
+    }
 }
 
 fn keycode_to_string(key: KeyCode) -> String {
@@ -147,14 +226,42 @@ fn default_key_paste() -> Vec<String> {
     vec!["p".to_string()]
 }
 
+fn default_key_paste_link() -> Vec<String> {
+    vec!["P".to_string()]
+}
+
 fn default_key_delete() -> Vec<String> {
     vec!["d".to_string()]
 }
 
+fn default_key_permanent_delete() -> Vec<String> {
+    vec!["D".to_string()]
+}
+
 fn default_key_rename() -> Vec<String> {
     vec!["r".to_string()]
 }
 
+fn default_key_bulk_rename() -> Vec<String> {
+    vec!["R".to_string()]
+}
+
+fn default_key_pattern_rename() -> Vec<String> {
+    vec!["S".to_string()]
+}
+
+fn default_key_permissions() -> Vec<String> {
+    vec!["C".to_string()]
+}
+
+fn default_key_archive() -> Vec<String> {
+    vec!["a".to_string()]
+}
+
+fn default_key_extract() -> Vec<String> {
+    vec!["A".to_string()]
+}
+
 fn default_key_new() -> Vec<String> {
     vec!["m".to_string()]
 }
@@ -183,6 +290,54 @@ fn default_key_undo() -> Vec<String> {
     vec!["u".to_string()]
 }
 
+fn default_key_redo() -> Vec<String> {
+    vec!["U".to_string()]
+}
+
+fn default_key_history() -> Vec<String> {
+    vec!["i".to_string()]
+}
+
+fn default_key_cancel_jobs() -> Vec<String> {
+    vec!["c".to_string()]
+}
+
+fn default_key_jobs() -> Vec<String> {
+    vec!["J".to_string()]
+}
+
+fn default_key_dry_run() -> Vec<String> {
+    vec!["!".to_string()]
+}
+
+fn default_key_sort() -> Vec<String> {
+    vec![",".to_string()]
+}
+
+fn default_key_search() -> Vec<String> {
+    vec!["/".to_string()]
+}
+
+fn default_key_search_next() -> Vec<String> {
+    vec!["n".to_string()]
+}
+
+fn default_key_search_prev() -> Vec<String> {
+    vec!["N".to_string()]
+}
+
+fn default_key_filter() -> Vec<String> {
+    vec!["f".to_string()]
+}
+
+fn default_key_clear_filter() -> Vec<String> {
+    vec!["F".to_string()]
+}
+
+fn default_key_trash() -> Vec<String> {
+    vec!["T".to_string()]
+}
+
 fn default_key_toggle_hidden() -> Vec<String> {
     vec!["H".to_string()]
 }
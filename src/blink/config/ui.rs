@@ -7,6 +7,19 @@ pub struct UiConfig {
     pub show_hidden: bool,
     #[serde(default = "default_border_type")]
     pub border_type: String,
+    // Shown to the right of each name in the current directory, in this order
+    #[serde(default = "default_columns")]
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Permissions,
+    Links,
+    Owner,
+    Size,
+    Date,
 }
 
 impl Default for UiConfig {
@@ -14,6 +27,7 @@ impl Default for UiConfig {
         Self {
             show_hidden: default_show_hidden(),
             border_type: default_border_type(),
+            columns: default_columns(),
         }
     }
 }
@@ -39,3 +53,7 @@ fn default_show_hidden() -> bool {
 fn default_border_type() -> String {
     "plain".to_string()
 }
+
+fn default_columns() -> Vec<Column> {
+    vec![Column::Size]
+}
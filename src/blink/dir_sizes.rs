@@ -0,0 +1,171 @@
+use crate::blink::{entries::FileEntry, thread_pool::ThreadPool};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::SystemTime,
+};
+
+#[derive(Default)]
+struct Shared {
+    // Keyed by path, a size only counts while the directory's mtime still matches
+    sizes: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+    // Directories queued or being walked along with the cancel flag of the request that queued
+    // them, so refreshing the listing doesn't queue them again
+    pending: HashMap<PathBuf, Arc<AtomicBool>>,
+    // Set when a size came in, a listing sorted by size has to be sorted again
+    updated: bool,
+}
+
+// Recursive sizes of the directories in the cwd, worked out in the background. Leaving the
+// directory cancels whatever hasn't finished yet
+pub struct DirSizes {
+    pool: ThreadPool,
+    shared: Arc<Mutex<Shared>>,
+    dir: PathBuf,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl DirSizes {
+    pub fn new(pool: ThreadPool) -> Self {
+        Self {
+            pool,
+            shared: Arc::new(Mutex::new(Shared::default())),
+            dir: PathBuf::new(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Queues every directory in entries that has no up to date size yet. They're walked one after
+    // another by a single job so a directory with thousands of subdirectories can't fill the queue
+    pub fn request(&mut self, dir: &Path, entries: &[FileEntry]) -> Result<(), String> {
+        if self.dir != dir {
+            self.cancel();
+            self.dir = dir.to_path_buf();
+        }
+
+        let mut queued = Vec::new();
+        {
+            let mut shared = self.shared.lock().unwrap();
+            // Only the listing's own directories are kept, so the cache doesn't grow with every
+            // directory visited
+            shared.sizes.retain(|path, _| {
+                entries
+                    .iter()
+                    .any(|entry| entry.is_dir && entry.path == *path)
+            });
+            for entry in entries.iter().filter(|entry| entry.is_dir) {
+                let up_to_date = shared
+                    .sizes
+                    .get(&entry.path)
+                    .is_some_and(|(modified, _)| *modified == entry.modified);
+                let running = shared
+                    .pending
+                    .get(&entry.path)
+                    .is_some_and(|token| Arc::ptr_eq(token, &self.cancelled));
+                if up_to_date || running {
+                    continue;
+                }
+                shared
+                    .pending
+                    .insert(entry.path.clone(), Arc::clone(&self.cancelled));
+                queued.push((entry.path.clone(), entry.modified));
+            }
+        }
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let shared = Arc::clone(&self.shared);
+        let cancelled = Arc::clone(&self.cancelled);
+        let status = self.pool.try_execute(move || {
+            for (path, modified) in queued {
+                let size = dir_size(&path, &cancelled);
+                let mut shared = shared.lock().unwrap();
+                // A later request for the same path owns the entry now
+                if shared
+                    .pending
+                    .get(&path)
+                    .is_some_and(|token| Arc::ptr_eq(token, &cancelled))
+                {
+                    shared.pending.remove(&path);
+                }
+                match size {
+                    Some(size) => {
+                        shared.sizes.insert(path, (modified, size));
+                        shared.updated = true;
+                    }
+                    None => {
+                        shared
+                            .pending
+                            .retain(|_, token| !Arc::ptr_eq(token, &cancelled));
+                        return;
+                    }
+                }
+            }
+        });
+
+        status.map_err(|e| {
+            let mut shared = self.shared.lock().unwrap();
+            shared
+                .pending
+                .retain(|_, token| !Arc::ptr_eq(token, &self.cancelled));
+            format!("Failed to queue directory sizes {}", e)
+        })
+    }
+
+    // Stops the walks that are still running, their directories get queued again next time
+    pub fn cancel(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+    }
+
+    // Whether sizes came in since the last call
+    pub fn take_updated(&self) -> bool {
+        std::mem::take(&mut self.shared.lock().unwrap().updated)
+    }
+
+    pub fn size_of(&self, entry: &FileEntry) -> Option<u64> {
+        self.shared
+            .lock()
+            .unwrap()
+            .sizes
+            .get(&entry.path)
+            .filter(|(modified, _)| *modified == entry.modified)
+            .map(|(_, size)| *size)
+    }
+}
+
+// Adds up everything below path without following symlinks. Unreadable parts are skipped, None
+// means the walk was cancelled
+fn dir_size(path: &Path, cancelled: &AtomicBool) -> Option<u64> {
+    let mut total = 0;
+    let mut stack = vec![path.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    Some(total)
+}
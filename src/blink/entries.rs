@@ -1,10 +1,12 @@
-use crate::blink::app::Preview;
+use crate::blink::{app::Preview, sorting::SortOrder};
 #[cfg(windows)]
 use std::os::windows::fs::MetadataExt;
 use std::{
+    collections::HashMap,
     fs, io,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
 #[derive(Debug, Clone)]
@@ -14,6 +16,72 @@ pub struct FileEntry {
     pub preview: Arc<Mutex<Preview>>,
     pub size: u64,
     pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    // Unix mode bits including the file type, None where there are none to show
+    pub mode: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub links: Option<u64>,
+    pub symlink_target: Option<PathBuf>,
+    // A symlink whose target can't be reached
+    pub broken_link: bool,
+}
+
+// Owner and group names for a listing. Most entries share a handful of ids, so each one is only
+// looked up once per directory
+#[derive(Default)]
+struct NameCache {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+}
+
+#[cfg(unix)]
+impl NameCache {
+    fn user(&mut self, uid: u32) -> Option<String> {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| crate::blink::users::user_name(uid))
+            .clone()
+            .or_else(|| Some(uid.to_string()))
+    }
+
+    fn group(&mut self, gid: u32) -> Option<String> {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| crate::blink::users::group_name(gid))
+            .clone()
+            .or_else(|| Some(gid.to_string()))
+    }
+}
+
+struct UnixInfo {
+    mode: Option<u32>,
+    owner: Option<String>,
+    group: Option<String>,
+    links: Option<u64>,
+}
+
+#[cfg(unix)]
+fn unix_info(metadata: &fs::Metadata, names: &mut NameCache) -> UnixInfo {
+    use std::os::unix::fs::MetadataExt;
+
+    UnixInfo {
+        mode: Some(metadata.mode()),
+        owner: names.user(metadata.uid()),
+        group: names.group(metadata.gid()),
+        links: Some(metadata.nlink()),
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_info(_metadata: &fs::Metadata, _names: &mut NameCache) -> UnixInfo {
+    UnixInfo {
+        mode: None,
+        owner: None,
+        group: None,
+        links: None,
+    }
 }
 
 #[cfg(windows)]
@@ -28,12 +96,17 @@ fn is_system_file(_metadata: &fs::Metadata) -> bool {
     false
 }
 
-pub fn get_entries(show_hidden: bool, path: &Path) -> io::Result<Vec<FileEntry>> {
+pub fn get_entries(
+    show_hidden: bool,
+    path: &Path,
+    order: &SortOrder,
+) -> io::Result<Vec<FileEntry>> {
     let read_dir = match fs::read_dir(path) {
         Ok(rd) => rd,
         Err(_) => return Ok(Vec::new()),
     };
     let mut entries = Vec::new();
+    let mut names = NameCache::default();
 
     for entry in read_dir {
         let entry = match entry {
@@ -65,6 +138,12 @@ pub fn get_entries(show_hidden: bool, path: &Path) -> io::Result<Vec<FileEntry>>
         let preview = Arc::new(Mutex::new(Preview::default()));
         let is_dir = metadata.is_dir();
         let size = metadata.len();
+        let info = unix_info(&metadata, &mut names);
+        let (symlink_target, broken_link) = if metadata.file_type().is_symlink() {
+            (fs::read_link(&path).ok(), fs::metadata(&path).is_err())
+        } else {
+            (None, false)
+        };
 
         entries.push(FileEntry {
             path,
@@ -72,15 +151,17 @@ pub fn get_entries(show_hidden: bool, path: &Path) -> io::Result<Vec<FileEntry>>
             preview,
             is_dir,
             size,
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            mode: info.mode,
+            owner: info.owner,
+            group: info.group,
+            links: info.links,
+            symlink_target,
+            broken_link,
         });
     }
 
-    // Sort so dirs are first followed by files and each section is alphabetical
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => std::cmp::Ordering::Less,
-        (false, true) => std::cmp::Ordering::Greater,
-        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-    });
-
+    order.apply(&mut entries);
     Ok(entries)
 }
@@ -0,0 +1,163 @@
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl MatchMode {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            MatchMode::Substring => "substring",
+            MatchMode::Glob => "glob",
+            MatchMode::Regex => "regex",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            MatchMode::Substring => MatchMode::Glob,
+            MatchMode::Glob => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Substring,
+        }
+    }
+}
+
+// A pattern entry names are checked against, used by both search and filter. Like vim's
+// smartcase it ignores case unless the pattern has an uppercase letter in it
+#[derive(Debug, Clone)]
+pub struct NameMatcher {
+    pub pattern: String,
+    pub mode: MatchMode,
+    regex: Regex,
+}
+
+impl NameMatcher {
+    pub fn new(pattern: &str, mode: MatchMode) -> Result<Self, String> {
+        let source = match mode {
+            MatchMode::Substring => regex::escape(pattern),
+            MatchMode::Glob => glob_to_regex(pattern),
+            MatchMode::Regex => pattern.to_string(),
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()
+            // Regex errors draw a caret under the pattern over several lines, keep the message
+            .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_string())?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            mode,
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+
+    // The part of name to highlight, globs always cover the whole name
+    pub fn find(&self, name: &str) -> Option<Range<usize>> {
+        self.regex.find(name).map(|found| found.range())
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} ({})", self.pattern, self.mode.describe())
+    }
+}
+
+// Globs have to match the whole name. *, ? and [...] work as in the shell, [!...] negates
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let negated = chars.next_if_eq(&'!').is_some();
+                let mut class = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    class.push(c);
+                }
+                // An unclosed bracket is just a bracket
+                if closed {
+                    regex.push('[');
+                    if negated {
+                        regex.push('^');
+                    }
+                    regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                    regex.push(']');
+                } else {
+                    let literal = format!("[{}{}", if negated { "!" } else { "" }, class);
+                    regex.push_str(&regex::escape(&literal));
+                }
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchMode, NameMatcher, glob_to_regex};
+
+    fn glob_matches(glob: &str, name: &str) -> bool {
+        NameMatcher::new(glob, MatchMode::Glob)
+            .unwrap()
+            .is_match(name)
+    }
+
+    #[test]
+    fn wildcards_match_the_whole_name() {
+        assert_eq!(glob_to_regex("*.rs"), r"^.*\.rs$");
+        assert!(glob_matches("*.rs", "main.rs"));
+        assert!(!glob_matches("*.rs", "main.rs.bak"));
+        assert!(glob_matches("file?.txt", "file1.txt"));
+        assert!(!glob_matches("file?.txt", "file10.txt"));
+    }
+
+    #[test]
+    fn brackets_match_one_of_their_characters() {
+        assert_eq!(glob_to_regex("[abc].txt"), r"^[abc]\.txt$");
+        assert!(glob_matches("[abc].txt", "b.txt"));
+        assert!(!glob_matches("[abc].txt", "d.txt"));
+        assert!(glob_matches("img[0-9]", "img7"));
+        assert!(!glob_matches("img[0-9]", "imgx"));
+    }
+
+    #[test]
+    fn exclamation_mark_negates_brackets() {
+        assert_eq!(glob_to_regex("[!abc]*"), r"^[^abc].*$");
+        assert!(glob_matches("[!abc]*", "docs"));
+        assert!(!glob_matches("[!abc]*", "build"));
+    }
+
+    #[test]
+    fn brackets_escape_regex_syntax() {
+        assert_eq!(glob_to_regex(r"[\[]"), r"^[\\\[]$");
+        assert!(glob_matches(r"[\[]x", "[x"));
+        assert!(glob_matches(r"[\[]x", r"\x"));
+    }
+
+    #[test]
+    fn unclosed_bracket_is_literal() {
+        assert_eq!(glob_to_regex("[abc"), r"^\[abc$");
+        assert!(glob_matches("[abc", "[abc"));
+        assert!(!glob_matches("[abc", "a"));
+        assert_eq!(glob_to_regex("a[!b"), r"^a\[!b$");
+        assert!(glob_matches("a[!b", "a[!b"));
+    }
+}
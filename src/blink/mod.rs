@@ -1,6 +1,10 @@
 pub mod app;
 pub mod states;
 pub mod entries;
+pub mod archive_view;
+pub mod dir_sizes;
+pub mod sorting;
+pub mod filter;
 pub mod rendering;
 pub mod file_style;
 pub mod config;
@@ -9,3 +13,5 @@ pub mod operations;
 pub mod trash_manager;
 pub mod thread_pool;
 pub mod logging;
+pub mod users;
+pub mod watcher;
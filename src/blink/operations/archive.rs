@@ -0,0 +1,862 @@
+use crate::blink::operations::progress::{Progress, Workload};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufReader, BufWriter, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => ".zip",
+            ArchiveFormat::Tar => ".tar",
+            ArchiveFormat::TarGz => ".tar.gz",
+            ArchiveFormat::TarXz => ".tar.xz",
+            ArchiveFormat::TarZst => ".tar.zst",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ArchiveFormat::Zip => ArchiveFormat::Tar,
+            ArchiveFormat::Tar => ArchiveFormat::TarGz,
+            ArchiveFormat::TarGz => ArchiveFormat::TarXz,
+            ArchiveFormat::TarXz => ArchiveFormat::TarZst,
+            ArchiveFormat::TarZst => ArchiveFormat::Zip,
+        }
+    }
+
+    // Picks the format from the file name, the short tar extensions like .tgz count too
+    pub fn from_name(name: &str) -> Option<Self> {
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| strip_extension(name, ext).is_some())
+            .map(|(_, format)| *format)
+    }
+
+    // The name with this format's extension in place of whatever archive extension it had
+    pub fn apply_to(self, name: &str) -> String {
+        format!("{}{}", stem(name).unwrap_or(name), self.extension())
+    }
+}
+
+// The archive's name without its extension, None when it isn't an archive
+pub fn stem(name: &str) -> Option<&str> {
+    EXTENSIONS
+        .iter()
+        .find_map(|(ext, _)| strip_extension(name, ext))
+}
+
+// Longer extensions come first so .tar.gz isn't mistaken for a plain .tar
+const EXTENSIONS: [(&str, ArchiveFormat); 8] = [
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tzst", ArchiveFormat::TarZst),
+    (".tar", ArchiveFormat::Tar),
+    (".zip", ArchiveFormat::Zip),
+];
+
+fn strip_extension<'a>(name: &'a str, ext: &str) -> Option<&'a str> {
+    let split = name.len().checked_sub(ext.len())?;
+    if split > 0 && name.is_char_boundary(split) && name[split..].eq_ignore_ascii_case(ext) {
+        Some(&name[..split])
+    } else {
+        None
+    }
+}
+
+// Writes every source, directories included, into a new archive. Entry names start at each
+// source's own name. A half written archive is removed when anything goes wrong
+pub fn create(
+    archive: &Path,
+    sources: &[PathBuf],
+    format: ArchiveFormat,
+    progress: &Progress,
+) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(archive)?;
+
+    let result = write_archive(BufWriter::new(file), archive, sources, format, progress);
+    // A cancel cuts the input short, which wins over whatever that made go wrong
+    let result = progress.check_cancelled().and(result);
+    if result.is_err() {
+        _ = fs::remove_file(archive);
+    }
+    result
+}
+
+fn write_archive(
+    writer: BufWriter<fs::File>,
+    archive: &Path,
+    sources: &[PathBuf],
+    format: ArchiveFormat,
+    progress: &Progress,
+) -> io::Result<()> {
+    let file = match format {
+        ArchiveFormat::Zip => write_zip(writer, archive, sources, progress)?,
+        ArchiveFormat::Tar => write_tar(writer, archive, sources, progress)?,
+        ArchiveFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            write_tar(encoder, archive, sources, progress)?.finish()?
+        }
+        ArchiveFormat::TarXz => {
+            let options = lzma_rust2::XzOptions::with_preset(6);
+            let encoder = lzma_rust2::XzWriter::new(writer, options)?;
+            write_tar(encoder, archive, sources, progress)?.finish()?
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(writer, 0)?;
+            write_tar(encoder, archive, sources, progress)?.finish()?
+        }
+    };
+    file.into_inner()?.sync_all()
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    archive: &Path,
+    sources: &[PathBuf],
+    progress: &Progress,
+) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+
+    for source in sources {
+        walk(source, archive, progress, &mut |path, name, metadata| {
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(metadata);
+
+            if metadata.file_type().is_symlink() {
+                header.set_size(0);
+                builder.append_link(&mut header, name, fs::read_link(path)?)?;
+                progress.add_files(1);
+            } else if metadata.is_dir() {
+                header.set_size(0);
+                builder.append_data(&mut header, name, io::empty())?;
+            } else {
+                let file = ProgressReader::new(fs::File::open(path)?, progress);
+                builder.append_data(&mut header, name, file)?;
+                progress.add_files(1);
+            }
+            Ok(())
+        })?;
+    }
+
+    builder.into_inner()
+}
+
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    archive: &Path,
+    sources: &[PathBuf],
+    progress: &Progress,
+) -> io::Result<W> {
+    use zip::{CompressionMethod, write::SimpleFileOptions};
+
+    let mut zip = zip::ZipWriter::new(writer);
+
+    for source in sources {
+        walk(source, archive, progress, &mut |path, name, metadata| {
+            let name = zip_name(name);
+            let mut options = SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .large_file(metadata.len() >= u32::MAX as u64);
+            if let Some(modified) = metadata.modified().ok().and_then(zip_time) {
+                options = options.last_modified_time(modified);
+            }
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                options = options.unix_permissions(metadata.permissions().mode() & 0o7777);
+            }
+
+            if metadata.file_type().is_symlink() {
+                let target = fs::read_link(path)?;
+                zip.add_symlink(name, target.to_string_lossy(), options)?;
+                progress.add_files(1);
+            } else if metadata.is_dir() {
+                zip.add_directory(format!("{}/", name), options)?;
+            } else {
+                zip.start_file(name, options)?;
+                io::copy(
+                    &mut ProgressReader::new(fs::File::open(path)?, progress),
+                    &mut zip,
+                )?;
+                progress.add_files(1);
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(zip.finish()?)
+}
+
+// Visits path and everything below it, parents before children. Symlinks are stored as links
+// and never followed, and the archive being written is left out in case it's inside a source
+fn walk(
+    path: &Path,
+    archive: &Path,
+    progress: &Progress,
+    visit: &mut dyn FnMut(&Path, &Path, &fs::Metadata) -> io::Result<()>,
+) -> io::Result<()> {
+    let name = PathBuf::from(path.file_name().unwrap_or(path.as_os_str()));
+    walk_entry(path, &name, archive, progress, visit)
+}
+
+fn walk_entry(
+    path: &Path,
+    name: &Path,
+    archive: &Path,
+    progress: &Progress,
+    visit: &mut dyn FnMut(&Path, &Path, &fs::Metadata) -> io::Result<()>,
+) -> io::Result<()> {
+    progress.check_cancelled()?;
+    if path == archive {
+        return Ok(());
+    }
+
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+    if !file_type.is_dir() && !file_type.is_file() && !file_type.is_symlink() {
+        // Sockets, fifos and devices don't have contents worth keeping
+        progress.add_files(1);
+        return Ok(());
+    }
+
+    visit(path, name, &metadata)?;
+
+    if metadata.is_dir() {
+        let mut entries = fs::read_dir(path)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            walk_entry(
+                &entry.path(),
+                &name.join(entry.file_name()),
+                archive,
+                progress,
+                visit,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Zip entry names always use forward slashes
+fn zip_name(name: &Path) -> String {
+    name.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Zip stores local time with two second precision and can't go before 1980
+fn zip_time(time: std::time::SystemTime) -> Option<zip::DateTime> {
+    use chrono::{Datelike, Timelike};
+
+    let time = chrono::DateTime::<chrono::Local>::from(time);
+    zip::DateTime::from_date_and_time(
+        time.year().try_into().ok()?,
+        time.month() as u8,
+        time.day() as u8,
+        time.hour() as u8,
+        time.minute() as u8,
+        (time.second() as u8).min(58),
+    )
+    .ok()
+}
+
+// Unpacks the archive, or just the given members of it, into destination, which is created
+// when missing. Existing directories are merged into but existing files are never overwritten.
+// Returns the paths that didn't exist before so they can be removed again, on failure those
+// are removed right away
+pub fn extract(
+    archive: &Path,
+    members: &[PathBuf],
+    destination: &Path,
+    progress: &Progress,
+) -> io::Result<Vec<PathBuf>> {
+    let format = format_of(archive)?;
+    let mut extractor = Extractor::new(destination, members, progress);
+    let result = extractor.create_destination().and_then(|_| {
+        let file = fs::File::open(archive)?;
+        match format {
+            ArchiveFormat::Zip => extractor.unpack_zip(file),
+            _ => extractor.unpack_tar(open_tar(file, format, progress)?),
+        }
+    });
+
+    match progress.check_cancelled().and(result) {
+        Ok(_) => {
+            extractor.finish();
+            Ok(extractor.created)
+        }
+        Err(e) => {
+            extractor.rollback();
+            Err(e)
+        }
+    }
+}
+
+// A file or directory inside an archive, listed without unpacking anything
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    // Relative to the archive's root
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+// Every member of the archive, including directories that are only implied by the paths of
+// their contents. Members whose names would point outside the archive are left out
+pub fn list(archive: &Path) -> io::Result<Vec<ArchiveMember>> {
+    let format = format_of(archive)?;
+    let file = fs::File::open(archive)?;
+    let mut members = Vec::new();
+
+    if format == ArchiveFormat::Zip {
+        let mut zip = zip::ZipArchive::new(BufReader::new(file))?;
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i)?;
+            if let Some(path) = member_path(Path::new(entry.name())) {
+                members.push(ArchiveMember {
+                    path,
+                    size: entry.size(),
+                    is_dir: entry.is_dir(),
+                });
+            }
+        }
+    } else {
+        let progress = Progress::default();
+        let mut tar = tar::Archive::new(open_tar(file, format, &progress)?);
+        for entry in tar.entries()? {
+            let entry = entry?;
+            if let Some(path) = member_path(&entry.path()?) {
+                members.push(ArchiveMember {
+                    path,
+                    size: entry.size(),
+                    is_dir: entry.header().entry_type().is_dir(),
+                });
+            }
+        }
+    }
+
+    let mut known: HashSet<PathBuf> = members.iter().map(|m| m.path.clone()).collect();
+    let mut implied = Vec::new();
+    for member in &members {
+        for ancestor in member.path.ancestors().skip(1) {
+            if !ancestor.as_os_str().is_empty() && known.insert(ancestor.to_path_buf()) {
+                implied.push(ArchiveMember {
+                    path: ancestor.to_path_buf(),
+                    size: 0,
+                    is_dir: true,
+                });
+            }
+        }
+    }
+    members.extend(implied);
+    Ok(members)
+}
+
+// Reads up to limit bytes of each of the members, used for previews. A compressed tar has to be
+// unpacked from the start to get to a member, so they're all picked up in a single pass that
+// stops once the last one was found. Members that weren't found are left out
+pub fn read_members(
+    archive: &Path,
+    members: &HashSet<PathBuf>,
+    limit: u64,
+) -> io::Result<HashMap<PathBuf, Vec<u8>>> {
+    let format = format_of(archive)?;
+    let file = fs::File::open(archive)?;
+    let mut contents = HashMap::new();
+
+    if format == ArchiveFormat::Zip {
+        let mut zip = zip::ZipArchive::new(BufReader::new(file))?;
+        for i in 0..zip.len() {
+            let Some(path) = member_path(Path::new(zip.by_index_raw(i)?.name())) else {
+                continue;
+            };
+            if members.contains(&path) && !contents.contains_key(&path) {
+                let mut bytes = Vec::new();
+                zip.by_index(i)?.take(limit).read_to_end(&mut bytes)?;
+                contents.insert(path, bytes);
+            }
+        }
+    } else {
+        let progress = Progress::default();
+        let mut tar = tar::Archive::new(open_tar(file, format, &progress)?);
+        for entry in tar.entries()? {
+            if contents.len() == members.len() {
+                break;
+            }
+            let entry = entry?;
+            let Some(path) = member_path(&entry.path()?) else {
+                continue;
+            };
+            if members.contains(&path) && !contents.contains_key(&path) {
+                let mut bytes = Vec::new();
+                entry.take(limit).read_to_end(&mut bytes)?;
+                contents.insert(path, bytes);
+            }
+        }
+    }
+
+    Ok(contents)
+}
+
+fn format_of(archive: &Path) -> io::Result<ArchiveFormat> {
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    ArchiveFormat::from_name(&name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} isn't a supported archive", name),
+        )
+    })
+}
+
+// The name of an entry as a plain relative path, None if it's absolute or climbs out with ..
+fn member_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+// Zips know their unpacked size up front, tar streams count how far into the archive file
+// they are and find out the number of files as they go
+pub fn extraction_workload(archive: &Path) -> Workload {
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    if ArchiveFormat::from_name(&name) == Some(ArchiveFormat::Zip) {
+        let Some(zip) = fs::File::open(archive)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+        else {
+            return Workload::default();
+        };
+        return Workload {
+            bytes: zip.decompressed_size().unwrap_or_default() as u64,
+            files: zip.file_names().filter(|name| !name.ends_with('/')).count() as u64,
+        };
+    }
+    Workload {
+        bytes: fs::metadata(archive).map(|m| m.len()).unwrap_or_default(),
+        files: 0,
+    }
+}
+
+// The decompressed tar stream, reads of the underlying file count towards the progress
+fn open_tar<'a>(
+    file: fs::File,
+    format: ArchiveFormat,
+    progress: &'a Progress,
+) -> io::Result<Box<dyn Read + 'a>> {
+    let reader = BufReader::new(ProgressReader::new(file, progress));
+    Ok(match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        ArchiveFormat::TarXz => Box::new(lzma_rust2::XzReader::new(reader, true)),
+        ArchiveFormat::TarZst => Box::new(zstd::Decoder::with_buffer(reader)?),
+        _ => Box::new(reader),
+    })
+}
+
+struct Extractor<'a> {
+    destination: PathBuf,
+    // Paths inside the archive to unpack, everything when empty
+    members: &'a [PathBuf],
+    progress: &'a Progress,
+    // Every path this extraction created in the order it did, parents before their contents
+    created: Vec<PathBuf>,
+    created_set: HashSet<PathBuf>,
+    // Modes are set on directories last, a read-only one would block its own contents
+    directory_modes: Vec<(PathBuf, u32)>,
+}
+
+impl<'a> Extractor<'a> {
+    fn new(destination: &Path, members: &'a [PathBuf], progress: &'a Progress) -> Self {
+        Self {
+            destination: destination.to_path_buf(),
+            members,
+            progress,
+            created: Vec::new(),
+            created_set: HashSet::new(),
+            directory_modes: Vec::new(),
+        }
+    }
+
+    fn create_destination(&mut self) -> io::Result<()> {
+        let destination = self.destination.clone();
+        self.create_dirs(&destination)
+    }
+
+    fn unpack_zip(&mut self, file: fs::File) -> io::Result<()> {
+        let mut zip = zip::ZipArchive::new(BufReader::new(file))?;
+
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i)?;
+            let Some(path) = self.target(Path::new(entry.name()))? else {
+                continue;
+            };
+            let mode = entry.unix_mode();
+            let modified = entry.last_modified().and_then(from_zip_time);
+
+            if entry.is_dir() {
+                self.unpack_dir(&path, mode)?;
+            } else if entry.is_symlink() {
+                let mut target = String::new();
+                entry.read_to_string(&mut target)?;
+                self.unpack_symlink(&path, Path::new(&target))?;
+            } else {
+                let reader = ProgressReader::new(&mut entry, self.progress);
+                self.unpack_file(&path, reader, mode, modified)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn unpack_tar(&mut self, reader: Box<dyn Read + '_>) -> io::Result<()> {
+        let mut archive = tar::Archive::new(reader);
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let Some(path) = self.target(&entry.path()?)? else {
+                continue;
+            };
+            let header = entry.header();
+            let mode = header.mode().ok();
+            let modified = header
+                .mtime()
+                .ok()
+                .map(|secs| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+
+            match header.entry_type() {
+                tar::EntryType::Directory => self.unpack_dir(&path, mode)?,
+                tar::EntryType::Symlink => {
+                    let target = entry.link_name()?.unwrap_or_default().into_owned();
+                    self.unpack_symlink(&path, &target)?;
+                }
+                tar::EntryType::Link => {
+                    let name = entry.link_name()?.unwrap_or_default().into_owned();
+                    let Some(target) = self.target(&name)? else {
+                        continue;
+                    };
+                    self.create_parent(&path)?;
+                    fs::hard_link(target, &path).map_err(|e| describe_error(e, &path))?;
+                    self.mark_created(&path);
+                }
+                tar::EntryType::Regular | tar::EntryType::Continuous => {
+                    self.unpack_file(&path, &mut entry, mode, modified)?;
+                }
+                // Devices, fifos and the like aren't worth recreating
+                _ => continue,
+            }
+        }
+        Ok(())
+    }
+
+    // Where an entry ends up, rejecting names that would land outside the destination.
+    // Entries that only name the destination itself, or weren't picked, are skipped
+    fn target(&self, name: &Path) -> io::Result<Option<PathBuf>> {
+        let escapes = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} points outside the target directory", name.display()),
+            )
+        };
+        let mut relative = member_path(name).ok_or_else(escapes)?;
+
+        // Picked members land in the destination under their own name
+        if !self.members.is_empty() {
+            let Some(picked) = self.members.iter().find_map(|member| {
+                let rest = relative.strip_prefix(member).ok()?;
+                Some(Path::new(member.file_name()?).join(rest))
+            }) else {
+                return Ok(None);
+            };
+            relative = picked;
+        }
+
+        // The destination itself may well be reached through a symlink, only the parts below
+        // it come from the archive
+        let mut path = self.destination.clone();
+        for (i, part) in relative.iter().enumerate() {
+            // A symlink unpacked earlier could lead the rest of the path anywhere
+            if i > 0 && fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink()) {
+                return Err(escapes());
+            }
+            path.push(part);
+        }
+
+        Ok((path != self.destination).then_some(path))
+    }
+
+    // Directories that were already there keep their mode, undo couldn't put it back
+    fn unpack_dir(&mut self, path: &Path, mode: Option<u32>) -> io::Result<()> {
+        self.create_dirs(path)?;
+        if let Some(mode) = mode
+            && self.is_created(path)
+        {
+            self.directory_modes.push((path.to_path_buf(), mode));
+        }
+        Ok(())
+    }
+
+    fn unpack_file(
+        &mut self,
+        path: &Path,
+        mut reader: impl Read,
+        mode: Option<u32>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        self.progress.check_cancelled()?;
+        self.create_parent(path)?;
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| describe_error(e, path))?;
+        self.mark_created(path);
+        io::copy(&mut reader, &mut file)?;
+
+        if let Some(modified) = modified {
+            _ = file.set_modified(modified);
+        }
+        if let Some(mode) = mode {
+            set_mode(path, mode);
+        }
+        self.progress.add_files(1);
+        Ok(())
+    }
+
+    fn unpack_symlink(&mut self, path: &Path, target: &Path) -> io::Result<()> {
+        self.create_parent(path)?;
+        symlink(target, path).map_err(|e| describe_error(e, path))?;
+        self.mark_created(path);
+        self.progress.add_files(1);
+        Ok(())
+    }
+
+    fn create_parent(&mut self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) => self.create_dirs(parent),
+            None => Ok(()),
+        }
+    }
+
+    // Like create_dir_all but remembers which directories are new. Symlinks are only followed
+    // up to the destination, below it they'd come from the archive
+    fn create_dirs(&mut self, path: &Path) -> io::Result<()> {
+        let metadata = if self.destination.starts_with(path) {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        };
+        match metadata {
+            Ok(m) if m.is_dir() => return Ok(()),
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists and isn't a directory", path.display()),
+                ));
+            }
+            Err(_) => {}
+        }
+
+        self.create_parent(path)?;
+        fs::create_dir(path)?;
+        self.mark_created(path);
+        Ok(())
+    }
+
+    fn is_created(&self, path: &Path) -> bool {
+        self.created_set.contains(path)
+    }
+
+    fn mark_created(&mut self, path: &Path) {
+        if self.created_set.insert(path.to_path_buf()) {
+            self.created.push(path.to_path_buf());
+        }
+    }
+
+    fn finish(&mut self) {
+        for (path, mode) in self.directory_modes.drain(..).rev() {
+            set_mode(&path, mode);
+        }
+    }
+
+    fn rollback(&mut self) {
+        _ = remove_created(&self.created);
+        self.created.clear();
+    }
+}
+
+// Existing files are never overwritten, so that's the error worth spelling out
+fn describe_error(e: io::Error, path: &Path) -> io::Error {
+    if e.kind() == io::ErrorKind::AlreadyExists {
+        io::Error::new(e.kind(), format!("{} already exists", path.display()))
+    } else {
+        e
+    }
+}
+
+// Removes the paths an extraction created, deepest first. Symlinks are removed and never
+// followed, and directories only go once they're empty, so whatever was put into them after
+// the extraction stays along with the directories leading to it
+pub fn remove_created(created: &[PathBuf]) -> io::Result<()> {
+    let created_set: HashSet<&Path> = created.iter().map(PathBuf::as_path).collect();
+    for path in created.iter().rev() {
+        // A read-only directory from the archive would keep its contents in place
+        if let Some(parent) = path.parent()
+            && created_set.contains(parent)
+        {
+            make_writable(parent);
+        }
+        match fs::symlink_metadata(path) {
+            Ok(m) if m.is_dir() => match fs::remove_dir(path) {
+                Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => {}
+                result => result?,
+            },
+            Ok(_) => fs::remove_file(path)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_writable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = fs::symlink_metadata(path)
+        && metadata.is_dir()
+    {
+        let mode = metadata.permissions().mode();
+        if mode & 0o700 != 0o700 {
+            _ = fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o700));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn make_writable(_path: &Path) {}
+
+// Setuid, setgid and sticky bits from an archive are dropped, like tar does for other users
+// than root
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+
+    _ = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777));
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) {}
+
+#[cfg(unix)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, path)
+}
+
+fn from_zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    use chrono::{Local, NaiveDate, TimeZone};
+
+    let time = NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?
+        .and_hms_opt(
+            time.hour().into(),
+            time.minute().into(),
+            time.second().into(),
+        )?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+// Counts bytes into the job's progress as they're read and stops once the job is cancelled
+struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a Progress,
+}
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    fn new(inner: R, progress: &'a Progress) -> Self {
+        Self { inner, progress }
+    }
+}
+
+// A cancel reads as the end of the input. Returning the Interrupted error here would make
+// io::copy and friends retry forever, so callers check the cancel flag once they're done
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.progress.is_cancelled() {
+            return Ok(0);
+        }
+        let read = self.inner.read(buf)?;
+        self.progress.add_bytes(read as u64);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::member_path;
+    use std::path::{Path, PathBuf};
+
+    fn member(name: &str) -> Option<PathBuf> {
+        member_path(Path::new(name))
+    }
+
+    #[test]
+    fn relative_names_are_kept() {
+        assert_eq!(
+            member("docs/readme.md"),
+            Some(PathBuf::from("docs/readme.md"))
+        );
+        assert_eq!(
+            member("./docs/./readme.md"),
+            Some(PathBuf::from("docs/readme.md"))
+        );
+        assert_eq!(member("docs/"), Some(PathBuf::from("docs")));
+        assert_eq!(member("..hidden"), Some(PathBuf::from("..hidden")));
+    }
+
+    #[test]
+    fn names_that_climb_out_are_rejected() {
+        assert_eq!(member("../evil"), None);
+        assert_eq!(member("docs/../../evil"), None);
+        assert_eq!(member("docs/.."), None);
+    }
+
+    #[test]
+    fn absolute_names_are_rejected() {
+        assert_eq!(member("/etc/passwd"), None);
+        assert_eq!(member("//etc/passwd"), None);
+    }
+}
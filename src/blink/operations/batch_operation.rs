@@ -0,0 +1,90 @@
+use crate::blink::operations::{
+    Operation,
+    dry_run::PlannedAction,
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+};
+use std::io;
+
+// Groups several operations so they execute, undo and redo as a single history entry.
+// If one of them fails, the ones that already ran are rolled back so nothing is left half done
+#[derive(Debug)]
+pub struct BatchOperation {
+    operations: Vec<Box<dyn Operation>>,
+}
+
+impl BatchOperation {
+    pub fn new(operations: Vec<Box<dyn Operation>>) -> Self {
+        Self { operations }
+    }
+
+    // Undoes the first `count` operations in reverse order
+    fn rollback(&self, count: usize, cause: io::Error) -> io::Error {
+        let failures: Vec<String> = self.operations[..count]
+            .iter()
+            .rev()
+            .filter_map(|op| op.undo().err())
+            .map(|e| e.to_string())
+            .collect();
+
+        if failures.is_empty() {
+            cause
+        } else {
+            io::Error::new(
+                cause.kind(),
+                format!("{} (rollback failed: {})", cause, failures.join(", ")),
+            )
+        }
+    }
+}
+
+impl Operation for BatchOperation {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        for i in 0..self.operations.len() {
+            if let Err(e) = progress
+                .check_cancelled()
+                .and_then(|_| self.operations[i].execute(progress))
+            {
+                return Err(self.rollback(i, e));
+            }
+        }
+        Ok(())
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        for op in self.operations.iter().rev() {
+            op.undo()?;
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        for i in 0..self.operations.len() {
+            if let Err(e) = self.operations[i].redo() {
+                return Err(self.rollback(i, e));
+            }
+        }
+        Ok(())
+    }
+
+    fn workload(&self) -> Workload {
+        self.operations
+            .iter()
+            .fold(Workload::default(), |acc, op| acc + op.workload())
+    }
+
+    // A batch can only be undone if every part of it can
+    fn undoable(&self) -> bool {
+        self.operations.iter().all(|op| op.undoable())
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        self.operations.iter().flat_map(|op| op.plan()).collect()
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Batch {
+            operations: self.operations.iter().map(|op| op.record()).collect(),
+        }
+    }
+}
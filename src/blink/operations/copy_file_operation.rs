@@ -1,88 +1,99 @@
-use crate::blink::operations::Operation;
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+    transfer::{self, CopyOptions},
+};
 use std::{fs, io, path::PathBuf};
 
 #[derive(Debug)]
 pub struct CopyFile {
     old_path: PathBuf,
-    dst_path: PathBuf,
     copy_path: PathBuf,
+    options: CopyOptions,
     executed: bool,
 }
 
 impl CopyFile {
-    pub fn new(old_path: PathBuf, dst_path: PathBuf) -> Self {
+    pub fn new(old_path: PathBuf, copy_path: PathBuf, options: CopyOptions) -> Self {
         Self {
             old_path,
-            dst_path,
-            copy_path: PathBuf::default(),
+            copy_path,
+            options,
             executed: false,
         }
     }
+
+    pub fn restore(old_path: PathBuf, copy_path: PathBuf, options: CopyOptions) -> Self {
+        Self {
+            old_path,
+            copy_path,
+            options,
+            executed: true,
+        }
+    }
 }
 
 impl Operation for CopyFile {
-    fn execute(&mut self) -> io::Result<()> {
-        if let Some(filename) = self.old_path.file_name() {
-            self.copy_path = self.dst_path.join(filename);
-            if self.copy_path.exists() {
-                let stem = self
-                    .copy_path
-                    .file_stem()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .into_owned();
-                let ext = self
-                    .copy_path
-                    .extension()
-                    .map(|e| format!(".{}", e.to_string_lossy()))
-                    .unwrap_or_default();
-                let mut counter = 1;
-                loop {
-                    self.copy_path = self
-                        .dst_path
-                        .join(format!("{}_copy{}{}", stem, counter, ext));
-                    if !self.copy_path.exists() {
-                        break;
-                    }
-                    counter += 1;
-                }
-            }
-            if self.old_path.is_dir() {
-                copy_directory_recursively(&self.old_path, &self.copy_path)?;
-            } else {
-                fs::copy(&self.old_path, &self.copy_path)?;
-            }
-        }
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        self.copy(progress)?;
         self.executed = true;
         Ok(())
     }
 
     fn undo(&self) -> io::Result<()> {
-        if self.executed && self.copy_path.exists() {
-            if self.copy_path.is_dir() {
-                fs::remove_dir_all(self.copy_path.clone())?;
-            } else {
-                fs::remove_file(self.copy_path.clone())?;
-            }
+        if self.executed && fs::symlink_metadata(&self.copy_path).is_ok() {
+            transfer::remove_path(&self.copy_path)?;
         }
         Ok(())
     }
-}
 
-fn copy_directory_recursively(src: &PathBuf, dst: &PathBuf) -> io::Result<()> {
-    fs::create_dir_all(dst)?;
+    fn redo(&mut self) -> io::Result<()> {
+        if self.executed {
+            self.copy(&Progress::default())?;
+        }
+        Ok(())
+    }
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+    fn workload(&self) -> Workload {
+        self.options.workload(&self.old_path)
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Create,
+            Some(self.old_path.clone()),
+            self.copy_path.clone(),
+        )]
+    }
 
-        if src_path.is_dir() {
-            copy_directory_recursively(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Copy {
+            old_path: self.old_path.clone(),
+            copy_path: self.copy_path.clone(),
+            follow_symlinks: self.options.follow_symlinks,
+            verify: self.options.verify,
         }
     }
+}
 
-    Ok(())
+impl CopyFile {
+    // Never copies over an existing path, conflicts are resolved before the copy is queued.
+    // Removes whatever got copied when the copy fails or is cancelled halfway
+    fn copy(&self, progress: &Progress) -> io::Result<()> {
+        if fs::symlink_metadata(&self.copy_path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", self.copy_path.display()),
+            ));
+        }
+
+        let status =
+            transfer::copy_recursively(&self.old_path, &self.copy_path, self.options, progress);
+        if status.is_err() && fs::symlink_metadata(&self.copy_path).is_ok() {
+            _ = transfer::remove_path(&self.copy_path);
+        }
+        status
+    }
 }
@@ -0,0 +1,83 @@
+use crate::blink::operations::{
+    Operation,
+    archive::{self, ArchiveFormat},
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+    transfer,
+};
+use std::{fs, io, path::PathBuf};
+
+// Packs the sources into a new archive. Undo deletes the archive and leaves the sources alone
+#[derive(Debug)]
+pub struct CreateArchive {
+    sources: Vec<PathBuf>,
+    archive_path: PathBuf,
+    format: ArchiveFormat,
+    created: bool,
+}
+
+impl CreateArchive {
+    pub fn new(sources: Vec<PathBuf>, archive_path: PathBuf, format: ArchiveFormat) -> Self {
+        Self {
+            sources,
+            archive_path,
+            format,
+            created: false,
+        }
+    }
+
+    pub fn restore(sources: Vec<PathBuf>, archive_path: PathBuf, format: ArchiveFormat) -> Self {
+        Self {
+            sources,
+            archive_path,
+            format,
+            created: true,
+        }
+    }
+}
+
+impl Operation for CreateArchive {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        archive::create(&self.archive_path, &self.sources, self.format, progress)?;
+        self.created = true;
+        Ok(())
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        if self.created && fs::symlink_metadata(&self.archive_path).is_ok() {
+            fs::remove_file(&self.archive_path)?;
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        if self.created {
+            self.execute(&Progress::default())?;
+        }
+        Ok(())
+    }
+
+    fn workload(&self) -> Workload {
+        self.sources
+            .iter()
+            .map(|path| transfer::measure(path))
+            .fold(Workload::default(), |acc, w| acc + w)
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Create,
+            None,
+            self.archive_path.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Archive {
+            sources: self.sources.clone(),
+            archive_path: self.archive_path.clone(),
+            format: self.format,
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use crate::blink::operations::Operation;
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::Progress,
+};
 use std::{fs, io, path::PathBuf};
 
 #[derive(Debug)]
@@ -14,10 +19,17 @@ impl CreateFile {
             created: false,
         }
     }
+
+    pub fn restore(path: PathBuf) -> Self {
+        Self {
+            path,
+            created: true,
+        }
+    }
 }
 
 impl Operation for CreateFile {
-    fn execute(&mut self) -> io::Result<()> {
+    fn execute(&mut self, _progress: &Progress) -> io::Result<()> {
         if let Some(_) = self.path.extension() {
             fs::File::create(&self.path)?;
         } else {
@@ -37,4 +49,31 @@ impl Operation for CreateFile {
         }
         Ok(())
     }
+
+    fn redo(&mut self) -> io::Result<()> {
+        if self.created {
+            if self.path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", self.path.display()),
+                ));
+            }
+            self.execute(&Progress::default())?;
+        }
+        Ok(())
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Create,
+            None,
+            self.path.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Create {
+            path: self.path.clone(),
+        }
+    }
 }
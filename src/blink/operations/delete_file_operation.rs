@@ -1,5 +1,14 @@
-use crate::blink::{operations::Operation, trash_manager::TrashManager};
-use std::{io, path::PathBuf};
+use crate::blink::{
+    operations::{
+        Operation,
+        dry_run::{ActionKind, PlannedAction},
+        journal::OperationRecord,
+        progress::{Progress, Workload},
+        transfer,
+    },
+    trash_manager::TrashManager,
+};
+use std::{fs, io, path::PathBuf};
 
 #[derive(Debug)]
 pub struct DeleteFile {
@@ -16,24 +25,74 @@ impl DeleteFile {
             trash_manager,
         }
     }
+
+    pub fn restore(
+        path: PathBuf,
+        trash_path: Option<PathBuf>,
+        trash_manager: TrashManager,
+    ) -> Self {
+        Self {
+            path,
+            trash_path,
+            trash_manager,
+        }
+    }
 }
 
 impl Operation for DeleteFile {
-    fn execute(&mut self) -> io::Result<()> {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
         if self.path.exists() {
-            let trash_path = self.trash_manager.move_to_trash(&self.path)?;
+            let trash_path = self.trash_manager.move_to_trash(&self.path, progress)?;
             self.trash_path = Some(trash_path);
         }
         Ok(())
     }
 
+    // The trashed copy may have been restored or purged from the trash browser since
     fn undo(&self) -> io::Result<()> {
         if let Some(trash_path) = &self.trash_path {
-            if trash_path.exists() {
-                self.trash_manager
-                    .restore_from_trash(trash_path, &self.path)?;
+            if fs::symlink_metadata(trash_path).is_err() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is no longer in the trash", self.path.display()),
+                ));
             }
+            self.trash_manager
+                .restore_from_trash(trash_path, &self.path)?;
+        }
+        Ok(())
+    }
+
+    // Trashes the file again, which gives it a fresh trash path
+    fn redo(&mut self) -> io::Result<()> {
+        if self.trash_path.is_some() {
+            self.trash_path = None;
+            self.execute(&Progress::default())?;
         }
         Ok(())
     }
+
+    fn workload(&self) -> Workload {
+        let (trash_dir, _) = self.trash_manager.trash_dir_for(&self.path);
+        if transfer::same_device(&self.path, &trash_dir) {
+            Workload { bytes: 0, files: 1 }
+        } else {
+            transfer::measure(&self.path)
+        }
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Trash,
+            None,
+            self.path.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Delete {
+            path: self.path.clone(),
+            trash_path: self.trash_path.clone(),
+        }
+    }
 }
@@ -0,0 +1,228 @@
+use crate::blink::operations::jobs::JobItem;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Create,
+    Move,
+    Link,
+    Trash,
+    Delete,
+    Change,
+    Unpack,
+}
+
+impl ActionKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ActionKind::Create => "Create",
+            ActionKind::Move => "Move",
+            ActionKind::Link => "Link",
+            ActionKind::Trash => "Trash",
+            ActionKind::Delete => "Delete",
+            ActionKind::Change => "Change",
+            ActionKind::Unpack => "Unpack",
+        }
+    }
+}
+
+// A single filesystem change an operation would make. Operations only describe these, whether
+// they'd work out is decided for the whole job at once since earlier steps free up or take paths
+#[derive(Debug, Clone)]
+pub struct PlannedAction {
+    pub kind: ActionKind,
+    pub source: Option<PathBuf>,
+    pub target: PathBuf,
+    // Set when the target was trashed or deleted earlier in the same job
+    pub overwrites: bool,
+    pub conflict: Option<String>,
+}
+
+impl PlannedAction {
+    pub fn new(kind: ActionKind, source: Option<PathBuf>, target: PathBuf) -> Self {
+        Self {
+            kind,
+            source,
+            target,
+            overwrites: false,
+            conflict: None,
+        }
+    }
+}
+
+// A job that has been put together but not started, so it can be looked at first
+#[derive(Debug)]
+pub struct PlannedJob {
+    pub description: String,
+    pub items: Vec<JobItem>,
+    // Background jobs go through the queue, the rest run right away as one history entry
+    pub background: bool,
+}
+
+impl PlannedJob {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Trashing or deleting entries and nothing else
+    pub fn only_removes(&self) -> bool {
+        self.items
+            .iter()
+            .flat_map(JobItem::plan)
+            .all(|action| matches!(action.kind, ActionKind::Trash | ActionKind::Delete))
+    }
+
+    // Everything the job would do, in order and with conflicts flagged. Only reads the filesystem
+    pub fn preview(&self) -> Vec<PlannedAction> {
+        check(self.items.iter().flat_map(JobItem::plan).collect())
+    }
+}
+
+// Walks the actions in order while keeping track of the paths earlier ones removed or took.
+// Only paths that were trashed or deleted count as overwritten, a path a move left behind is free
+fn check(mut actions: Vec<PlannedAction>) -> Vec<PlannedAction> {
+    let mut removed: HashSet<PathBuf> = HashSet::new();
+    let mut deleted: HashSet<PathBuf> = HashSet::new();
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    let present = |path: &Path, removed: &HashSet<PathBuf>, taken: &HashSet<PathBuf>| {
+        taken.contains(path) || (fs::symlink_metadata(path).is_ok() && !removed.contains(path))
+    };
+
+    for action in actions.iter_mut() {
+        let source = match action.kind {
+            ActionKind::Trash | ActionKind::Delete | ActionKind::Change => Some(&action.target),
+            _ => action.source.as_ref(),
+        };
+        if let Some(source) = source
+            && !present(source, &removed, &taken)
+        {
+            action.conflict = Some(format!("{} doesn't exist", source.display()));
+        }
+
+        match action.kind {
+            ActionKind::Create | ActionKind::Move | ActionKind::Link => {
+                if present(&action.target, &removed, &taken) {
+                    action
+                        .conflict
+                        .get_or_insert_with(|| "Target already exists".to_string());
+                }
+                removed.remove(&action.target);
+                action.overwrites = deleted.remove(&action.target);
+                taken.insert(action.target.clone());
+                if action.kind == ActionKind::Move
+                    && let Some(source) = &action.source
+                {
+                    taken.remove(source);
+                    removed.insert(source.clone());
+                }
+            }
+            ActionKind::Trash | ActionKind::Delete => {
+                taken.remove(&action.target);
+                removed.insert(action.target.clone());
+                deleted.insert(action.target.clone());
+            }
+            ActionKind::Change | ActionKind::Unpack => {}
+        }
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionKind, PlannedAction, check};
+    use std::{fs, path::PathBuf};
+
+    // A scratch directory holding files a and b
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("blink-dry-run-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("a"), "").unwrap();
+            fs::write(dir.join("b"), "").unwrap();
+            Self(dir)
+        }
+
+        fn action(&self, kind: ActionKind, source: Option<&str>, target: &str) -> PlannedAction {
+            PlannedAction::new(kind, source.map(|s| self.0.join(s)), self.0.join(target))
+        }
+
+        fn moved(&self, source: &str, target: &str) -> PlannedAction {
+            self.action(ActionKind::Move, Some(source), target)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn flags(actions: &[PlannedAction]) -> Vec<(Option<&str>, bool)> {
+        actions
+            .iter()
+            .map(|action| (action.conflict.as_deref(), action.overwrites))
+            .collect()
+    }
+
+    #[test]
+    fn existing_targets_conflict() {
+        let dir = Scratch::new("existing");
+        let actions = check(vec![dir.moved("a", "c"), dir.moved("b", "c")]);
+        assert_eq!(
+            flags(&actions),
+            [(None, false), (Some("Target already exists"), false)]
+        );
+
+        let actions = check(vec![dir.action(ActionKind::Link, Some("a"), "b")]);
+        assert_eq!(flags(&actions), [(Some("Target already exists"), false)]);
+    }
+
+    #[test]
+    fn missing_sources_conflict() {
+        let dir = Scratch::new("missing");
+        let actions = check(vec![
+            dir.moved("a", "c"),
+            dir.moved("a", "d"),
+            dir.action(ActionKind::Delete, None, "x"),
+        ]);
+        assert_eq!(actions[0].conflict, None);
+        let missing = |name: &str| format!("{} doesn't exist", dir.0.join(name).display());
+        assert_eq!(actions[1].conflict, Some(missing("a")));
+        assert_eq!(actions[2].conflict, Some(missing("x")));
+    }
+
+    #[test]
+    fn removed_targets_are_overwritten() {
+        let dir = Scratch::new("removed");
+        let actions = check(vec![
+            dir.action(ActionKind::Trash, None, "b"),
+            dir.moved("a", "b"),
+        ]);
+        assert_eq!(flags(&actions), [(None, false), (None, true)]);
+    }
+
+    #[test]
+    fn paths_moved_away_are_free() {
+        let dir = Scratch::new("moved");
+        let actions = check(vec![
+            dir.moved("a", "c"),
+            dir.action(ActionKind::Create, None, "a"),
+            dir.moved("b", "c"),
+        ]);
+        assert_eq!(
+            flags(&actions),
+            [
+                (None, false),
+                (None, false),
+                (Some("Target already exists"), false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::blink::operations::{
+    Operation,
+    archive::{self, remove_created},
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+};
+use std::{io, path::PathBuf};
+
+// Unpacks an archive, or only some of its members, into destination. Undo removes whatever
+// the extraction created, files that were already in the destination or were added to the
+// extracted directories since stay where they are
+#[derive(Debug)]
+pub struct ExtractArchive {
+    archive_path: PathBuf,
+    members: Vec<PathBuf>,
+    destination: PathBuf,
+    created: Vec<PathBuf>,
+}
+
+impl ExtractArchive {
+    pub fn new(archive_path: PathBuf, members: Vec<PathBuf>, destination: PathBuf) -> Self {
+        Self {
+            archive_path,
+            members,
+            destination,
+            created: Vec::new(),
+        }
+    }
+
+    pub fn restore(
+        archive_path: PathBuf,
+        members: Vec<PathBuf>,
+        destination: PathBuf,
+        created: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            archive_path,
+            members,
+            destination,
+            created,
+        }
+    }
+}
+
+impl Operation for ExtractArchive {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        self.created = archive::extract(
+            &self.archive_path,
+            &self.members,
+            &self.destination,
+            progress,
+        )?;
+        Ok(())
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        remove_created(&self.created)
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        self.execute(&Progress::default())
+    }
+
+    fn workload(&self) -> Workload {
+        archive::extraction_workload(&self.archive_path)
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Unpack,
+            Some(self.archive_path.clone()),
+            self.destination.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Extract {
+            archive_path: self.archive_path.clone(),
+            members: self.members.clone(),
+            destination: self.destination.clone(),
+            created: self.created.clone(),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::blink::operations::{
+    Operation,
+    dry_run::PlannedAction,
+    progress::{Progress, Workload},
+};
+use std::{
+    io,
+    path::Path,
+    thread::{self, JoinHandle},
+};
+
+// One independent piece of a job. Items succeed or fail on their own, so a selection that has to
+// go through as a whole, like a paste or delete, is a single item holding a batch
+#[derive(Debug)]
+pub struct JobItem {
+    pub label: String,
+    op: Box<dyn Operation>,
+}
+
+impl JobItem {
+    pub fn new(label: String, op: Box<dyn Operation>) -> Self {
+        Self { label, op }
+    }
+
+    pub fn for_path(path: &Path, op: Box<dyn Operation>) -> Self {
+        let label = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+        Self::new(label, op)
+    }
+
+    pub fn plan(&self) -> Vec<PlannedAction> {
+        self.op.plan()
+    }
+
+    pub fn into_operation(self) -> Box<dyn Operation> {
+        self.op
+    }
+}
+
+// An operation executing on its own thread so long copies don't freeze the UI.
+// The items come back through the handle once they're done so the ones that worked can go
+// into the history and the rest can be retried
+pub struct Job {
+    pub description: String,
+    pub progress: Progress,
+    handle: JoinHandle<Vec<(JobItem, io::Result<()>)>>,
+}
+
+pub struct FinishedJob {
+    pub description: String,
+    pub result: io::Result<()>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+pub struct ItemFailure {
+    pub label: String,
+    pub error: io::Error,
+    op: Box<dyn Operation>,
+}
+
+// What's left of a job after it ran, kept around for the jobs panel
+pub struct JobReport {
+    pub description: String,
+    pub status: JobStatus,
+    pub item_count: usize,
+    pub failures: Vec<ItemFailure>,
+}
+
+impl JobReport {
+    // Splits the results into the operations that went through and a report of the rest
+    pub fn new(
+        description: String,
+        results: Vec<(JobItem, io::Result<()>)>,
+    ) -> (Self, Vec<Box<dyn Operation>>) {
+        let item_count = results.len();
+        let mut succeeded = Vec::new();
+        let mut failures = Vec::new();
+        for (item, result) in results {
+            match result {
+                Ok(_) => succeeded.push(item.op),
+                Err(error) => failures.push(ItemFailure {
+                    label: item.label,
+                    error,
+                    op: item.op,
+                }),
+            }
+        }
+
+        let status = if failures.is_empty() {
+            JobStatus::Finished
+        } else if failures
+            .iter()
+            .all(|failure| failure.error.kind() == io::ErrorKind::Interrupted)
+        {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Failed
+        };
+
+        let report = Self {
+            description,
+            status,
+            item_count,
+            failures,
+        };
+        (report, succeeded)
+    }
+
+    // The job's outcome as a single result, for the log
+    pub fn result(&self) -> io::Result<()> {
+        let Some(first) = self
+            .failures
+            .iter()
+            .find(|failure| failure.error.kind() != io::ErrorKind::Interrupted)
+        else {
+            return match self.status {
+                JobStatus::Cancelled => {
+                    Err(io::Error::new(io::ErrorKind::Interrupted, "Cancelled"))
+                }
+                _ => Ok(()),
+            };
+        };
+
+        let message = if self.item_count == 1 {
+            first.error.to_string()
+        } else {
+            format!(
+                "{} of {} items failed, {}: {}",
+                self.failures.len(),
+                self.item_count,
+                first.label,
+                first.error
+            )
+        };
+        Err(io::Error::new(first.error.kind(), message))
+    }
+
+    // Hands the failed items back so they can be queued again
+    pub fn take_failed(&mut self) -> Vec<JobItem> {
+        self.failures
+            .drain(..)
+            .map(|failure| JobItem::new(failure.label, failure.op))
+            .collect()
+    }
+}
+
+impl Job {
+    pub fn spawn(description: String, items: Vec<JobItem>) -> Self {
+        let progress = Progress::default();
+        let job_progress = progress.clone();
+
+        let handle = thread::spawn(move || {
+            job_progress.set_total(
+                items
+                    .iter()
+                    .fold(Workload::default(), |acc, item| acc + item.op.workload()),
+            );
+            items
+                .into_iter()
+                .map(|mut item| {
+                    let result = job_progress
+                        .check_cancelled()
+                        .and_then(|_| item.op.execute(&job_progress));
+                    (item, result)
+                })
+                .collect()
+        });
+
+        Self {
+            description,
+            progress,
+            handle,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub fn join(self) -> io::Result<Vec<(JobItem, io::Result<()>)>> {
+        self.handle
+            .join()
+            .map_err(|_| io::Error::other("Job panicked"))
+    }
+}
@@ -0,0 +1,524 @@
+use crate::blink::{
+    operations::{
+        Operation,
+        archive::ArchiveFormat,
+        batch_operation::BatchOperation,
+        copy_file_operation::CopyFile,
+        create_archive_operation::CreateArchive,
+        create_file_operation::CreateFile,
+        delete_file_operation::DeleteFile,
+        extract_archive_operation::ExtractArchive,
+        link_operation::{CreateLink, LinkKind},
+        permissions_operation::{ChangePermissions, PermissionChange, PermissionState},
+        purge_files_operation::PurgeFiles,
+        rename_file_operation::RenameFile,
+        transfer::CopyOptions,
+    },
+    trash_manager::TrashManager,
+    users,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Only the most recent sessions are kept around for browsing
+const MAX_SESSIONS: usize = 20;
+
+// Everything needed to rebuild an already executed operation after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OperationRecord {
+    Create {
+        path: PathBuf,
+    },
+    Delete {
+        path: PathBuf,
+        trash_path: Option<PathBuf>,
+    },
+    Rename {
+        old_path: PathBuf,
+        new_path: PathBuf,
+        #[serde(default)]
+        verify: bool,
+    },
+    Copy {
+        old_path: PathBuf,
+        copy_path: PathBuf,
+        #[serde(default)]
+        follow_symlinks: bool,
+        #[serde(default)]
+        verify: bool,
+    },
+    Batch {
+        operations: Vec<OperationRecord>,
+    },
+    Purge {
+        paths: Vec<PathBuf>,
+    },
+    Link {
+        source: PathBuf,
+        link_path: PathBuf,
+        link_kind: LinkKind,
+    },
+    Permissions {
+        paths: Vec<PathBuf>,
+        change: PermissionChange,
+        recursive: bool,
+        previous: Vec<PermissionState>,
+    },
+    Archive {
+        sources: Vec<PathBuf>,
+        archive_path: PathBuf,
+        format: ArchiveFormat,
+    },
+    Extract {
+        archive_path: PathBuf,
+        #[serde(default)]
+        members: Vec<PathBuf>,
+        destination: PathBuf,
+        created: Vec<PathBuf>,
+    },
+}
+
+impl OperationRecord {
+    pub fn into_operation(self, trash_manager: &TrashManager) -> Box<dyn Operation> {
+        match self {
+            OperationRecord::Create { path } => Box::new(CreateFile::restore(path)),
+            OperationRecord::Delete { path, trash_path } => {
+                Box::new(DeleteFile::restore(path, trash_path, trash_manager.clone()))
+            }
+            OperationRecord::Rename {
+                old_path,
+                new_path,
+                verify,
+            } => Box::new(RenameFile::restore(old_path, new_path, verify)),
+            OperationRecord::Copy {
+                old_path,
+                copy_path,
+                follow_symlinks,
+                verify,
+            } => Box::new(CopyFile::restore(
+                old_path,
+                copy_path,
+                CopyOptions {
+                    follow_symlinks,
+                    verify,
+                },
+            )),
+            OperationRecord::Batch { operations } => Box::new(BatchOperation::new(
+                operations
+                    .into_iter()
+                    .map(|record| record.into_operation(trash_manager))
+                    .collect(),
+            )),
+            OperationRecord::Purge { paths } => Box::new(PurgeFiles::new(paths)),
+            OperationRecord::Link {
+                source,
+                link_path,
+                link_kind,
+            } => Box::new(CreateLink::restore(source, link_path, link_kind)),
+            OperationRecord::Permissions {
+                paths,
+                change,
+                recursive,
+                previous,
+            } => Box::new(ChangePermissions::restore(
+                paths, change, recursive, previous,
+            )),
+            OperationRecord::Archive {
+                sources,
+                archive_path,
+                format,
+            } => Box::new(CreateArchive::restore(sources, archive_path, format)),
+            OperationRecord::Extract {
+                archive_path,
+                members,
+                destination,
+                created,
+            } => Box::new(ExtractArchive::restore(
+                archive_path,
+                members,
+                destination,
+                created,
+            )),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            OperationRecord::Create { path } => format!("Create {}", path.display()),
+            OperationRecord::Delete { path, .. } => format!("Delete {}", path.display()),
+            OperationRecord::Rename {
+                old_path, new_path, ..
+            } => {
+                format!("Move {} -> {}", old_path.display(), new_path.display())
+            }
+            OperationRecord::Copy {
+                old_path,
+                copy_path,
+                ..
+            } => format!("Copy {} -> {}", old_path.display(), copy_path.display()),
+            OperationRecord::Batch { operations } => match operations.first() {
+                Some(first) => format!("{} (+{} more)", first.describe(), operations.len() - 1),
+                None => "Empty batch".to_string(),
+            },
+            OperationRecord::Purge { paths } => match paths.as_slice() {
+                [path] => format!("Permanently delete {}", path.display()),
+                _ => format!("Permanently delete {} items", paths.len()),
+            },
+            OperationRecord::Link {
+                source,
+                link_path,
+                link_kind,
+            } => format!(
+                "{} {} -> {}",
+                link_kind.describe(),
+                link_path.display(),
+                source.display()
+            ),
+            OperationRecord::Permissions { paths, change, .. } => {
+                let mut parts = Vec::new();
+                if let Some(mode) = change.mode {
+                    parts.push(format!("mode {:04o}", mode));
+                }
+                if change.set != 0 {
+                    parts.push(format!("+{:04o}", change.set));
+                }
+                if change.clear != 0 {
+                    parts.push(format!("-{:04o}", change.clear));
+                }
+                if let Some(owner) = change.owner {
+                    let name = users::user_name(owner).unwrap_or_else(|| owner.to_string());
+                    parts.push(format!("owner {}", name));
+                }
+                if let Some(group) = change.group {
+                    let name = users::group_name(group).unwrap_or_else(|| group.to_string());
+                    parts.push(format!("group {}", name));
+                }
+                let target = match paths.as_slice() {
+                    [path] => path.display().to_string(),
+                    _ => format!("{} items", paths.len()),
+                };
+                format!("Set {} on {}", parts.join(", "), target)
+            }
+            OperationRecord::Archive {
+                sources,
+                archive_path,
+                ..
+            } => match sources.as_slice() {
+                [source] => format!(
+                    "Compress {} -> {}",
+                    source.display(),
+                    archive_path.display()
+                ),
+                _ => format!(
+                    "Compress {} items -> {}",
+                    sources.len(),
+                    archive_path.display()
+                ),
+            },
+            OperationRecord::Extract {
+                archive_path,
+                members,
+                destination,
+                ..
+            } => match members.as_slice() {
+                [] => format!(
+                    "Extract {} -> {}",
+                    archive_path.display(),
+                    destination.display()
+                ),
+                _ => format!(
+                    "Extract {} item(s) from {} -> {}",
+                    members.len(),
+                    archive_path.display(),
+                    destination.display()
+                ),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    Execute,
+    Undo,
+    Redo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: u64,
+    pub action: JournalAction,
+    pub operation: OperationRecord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalSession {
+    pub started: u64,
+    #[serde(default)]
+    pub entries: Vec<JournalEntry>,
+}
+
+// The undo/redo stacks as they were when blink last touched them, plus a log of what
+// happened in each session. Lives in the data dir so undo keeps working after a restart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    #[serde(default)]
+    pub history: Vec<OperationRecord>,
+    #[serde(default)]
+    pub redo_stack: Vec<OperationRecord>,
+    #[serde(default)]
+    pub sessions: Vec<JournalSession>,
+}
+
+impl Journal {
+    // Loads the saved journal and opens a new session for this run. A journal that doesn't
+    // parse is moved aside so blink still starts, the message says where it went
+    pub fn load() -> io::Result<(Self, Option<String>)> {
+        let path = Self::journal_path()?;
+        let empty = || Journal {
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            sessions: Vec::new(),
+        };
+
+        let mut warning = None;
+        let mut journal = if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            match toml::from_str(&contents) {
+                Ok(journal) => journal,
+                Err(e) => {
+                    let backup = path.with_extension(format!("toml.corrupt-{}", now()));
+                    warning = Some(match fs::rename(&path, &backup) {
+                        Ok(()) => format!(
+                            "Journal is corrupt, moved it to {} and started a new one: {}",
+                            backup.display(),
+                            e.message().replace('\n', " ")
+                        ),
+                        Err(rename_error) => format!(
+                            "Journal is corrupt and couldn't be moved aside ({}), starting a new one: {}",
+                            rename_error,
+                            e.message().replace('\n', " ")
+                        ),
+                    });
+                    empty()
+                }
+            }
+        } else {
+            empty()
+        };
+
+        journal
+            .sessions
+            .retain(|session| !session.entries.is_empty());
+        journal.sessions.push(JournalSession {
+            started: now(),
+            entries: Vec::new(),
+        });
+        Ok((journal, warning))
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        let path = Self::journal_path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if self.sessions.len() > MAX_SESSIONS {
+            self.sessions.drain(..self.sessions.len() - MAX_SESSIONS);
+        }
+
+        // Written next to the journal first and renamed over it, so a crash or a full disk
+        // mid-save leaves the old journal in place rather than half of a new one
+        let toml_string = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        let temp_path = path.with_extension("toml.tmp");
+        let written = fs::File::create(&temp_path).and_then(|mut file| {
+            file.write_all(toml_string.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&temp_path, &path)) {
+            _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Number of rows the history browser shows, one header per session plus its entries
+    pub fn row_count(&self) -> usize {
+        self.sessions
+            .iter()
+            .map(|session| session.entries.len() + 1)
+            .sum()
+    }
+
+    pub fn record(&mut self, action: JournalAction, operation: OperationRecord) {
+        if let Some(session) = self.sessions.last_mut() {
+            session.entries.push(JournalEntry {
+                timestamp: now(),
+                action,
+                operation,
+            });
+        }
+    }
+
+    fn journal_path() -> io::Result<PathBuf> {
+        use directories::ProjectDirs;
+
+        let proj_dirs = ProjectDirs::from("com", "Varcrow", "blink").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Could not determine data directory",
+            )
+        })?;
+
+        Ok(proj_dirs.data_dir().join("journal.toml"))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Journal, JournalAction, JournalEntry, JournalSession, OperationRecord};
+    use crate::blink::operations::{
+        archive::ArchiveFormat,
+        link_operation::LinkKind,
+        permissions_operation::{PermissionChange, PermissionState},
+    };
+    use std::path::PathBuf;
+
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from("/home/me").join(name)
+    }
+
+    // One of every kind of record, with optional fields both set and left out
+    fn records() -> Vec<OperationRecord> {
+        vec![
+            OperationRecord::Create { path: path("new") },
+            OperationRecord::Delete {
+                path: path("old"),
+                trash_path: Some(path(".local/share/Trash/files/old")),
+            },
+            OperationRecord::Delete {
+                path: path("gone"),
+                trash_path: None,
+            },
+            OperationRecord::Rename {
+                old_path: path("a"),
+                new_path: path("b"),
+                verify: true,
+            },
+            OperationRecord::Batch {
+                operations: vec![
+                    OperationRecord::Copy {
+                        old_path: path("a"),
+                        copy_path: path("backup/a"),
+                        follow_symlinks: true,
+                        verify: false,
+                    },
+                    OperationRecord::Purge {
+                        paths: vec![path("x"), path("y")],
+                    },
+                ],
+            },
+            OperationRecord::Link {
+                source: path("a"),
+                link_path: path("a.lnk"),
+                link_kind: LinkKind::RelativeSymlink,
+            },
+            OperationRecord::Permissions {
+                paths: vec![path("run.sh")],
+                change: PermissionChange {
+                    mode: None,
+                    set: 0o111,
+                    clear: 0,
+                    owner: Some(1000),
+                    group: None,
+                },
+                recursive: false,
+                previous: vec![PermissionState {
+                    path: path("run.sh"),
+                    mode: 0o644,
+                    owner: 0,
+                    group: 0,
+                }],
+            },
+            OperationRecord::Archive {
+                sources: vec![path("docs")],
+                archive_path: path("docs.tar.zst"),
+                format: ArchiveFormat::TarZst,
+            },
+            OperationRecord::Extract {
+                archive_path: path("docs.zip"),
+                members: vec![PathBuf::from("docs/readme.md")],
+                destination: path("docs"),
+                created: vec![path("docs"), path("docs/readme.md")],
+            },
+        ]
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let journal = Journal {
+            history: records(),
+            redo_stack: records().into_iter().rev().collect(),
+            sessions: vec![JournalSession {
+                started: 1_700_000_000,
+                entries: records()
+                    .into_iter()
+                    .map(|operation| JournalEntry {
+                        timestamp: 1_700_000_001,
+                        action: JournalAction::Undo,
+                        operation,
+                    })
+                    .collect(),
+            }],
+        };
+
+        let saved = toml::to_string_pretty(&journal).unwrap();
+        let loaded: Journal = toml::from_str(&saved).unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", journal));
+        assert_eq!(toml::to_string_pretty(&loaded).unwrap(), saved);
+    }
+
+    #[test]
+    fn fields_added_later_have_defaults() {
+        let loaded: Journal = toml::from_str(
+            r#"
+            [[history]]
+            kind = "rename"
+            old_path = "/a"
+            new_path = "/b"
+
+            [[history]]
+            kind = "extract"
+            archive_path = "/a.zip"
+            destination = "/a"
+            created = []
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &loaded.history[0],
+            OperationRecord::Rename { verify: false, .. }
+        ));
+        assert!(matches!(
+            &loaded.history[1],
+            OperationRecord::Extract { members, .. } if members.is_empty()
+        ));
+        assert!(loaded.redo_stack.is_empty() && loaded.sessions.is_empty());
+    }
+}
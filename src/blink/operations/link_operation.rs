@@ -0,0 +1,175 @@
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::Progress,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    AbsoluteSymlink,
+    RelativeSymlink,
+    HardLink,
+}
+
+impl LinkKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            LinkKind::AbsoluteSymlink => "Symlink",
+            LinkKind::RelativeSymlink => "Relative symlink",
+            LinkKind::HardLink => "Hard link",
+        }
+    }
+}
+
+// Creates link_path pointing at source. Undo removes the link and never touches the source
+#[derive(Debug)]
+pub struct CreateLink {
+    source: PathBuf,
+    link_path: PathBuf,
+    kind: LinkKind,
+    created: bool,
+}
+
+impl CreateLink {
+    pub fn new(source: PathBuf, link_path: PathBuf, kind: LinkKind) -> Self {
+        Self {
+            source,
+            link_path,
+            kind,
+            created: false,
+        }
+    }
+
+    pub fn restore(source: PathBuf, link_path: PathBuf, kind: LinkKind) -> Self {
+        Self {
+            source,
+            link_path,
+            kind,
+            created: true,
+        }
+    }
+
+    fn link(&self) -> io::Result<()> {
+        if fs::symlink_metadata(&self.link_path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", self.link_path.display()),
+            ));
+        }
+
+        let source = std::path::absolute(&self.source)?;
+        match self.kind {
+            LinkKind::AbsoluteSymlink => symlink(&source, &source, &self.link_path),
+            LinkKind::RelativeSymlink => {
+                let link_dir = std::path::absolute(&self.link_path)?
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default();
+                symlink(&relative_path(&link_dir, &source), &source, &self.link_path)
+            }
+            LinkKind::HardLink => fs::hard_link(&source, &self.link_path),
+        }
+    }
+}
+
+impl Operation for CreateLink {
+    fn execute(&mut self, _progress: &Progress) -> io::Result<()> {
+        self.link()?;
+        self.created = true;
+        Ok(())
+    }
+
+    // Only removes what is still a link, in case something else took its place since
+    fn undo(&self) -> io::Result<()> {
+        if !self.created {
+            return Ok(());
+        }
+        match fs::symlink_metadata(&self.link_path) {
+            Ok(m) if m.file_type().is_symlink() || self.kind == LinkKind::HardLink => {
+                remove_link(&self.link_path)
+            }
+            Ok(_) => Err(io::Error::other(format!(
+                "{} is no longer a link",
+                self.link_path.display()
+            ))),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        if self.created {
+            self.link()?;
+        }
+        Ok(())
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Link,
+            Some(self.source.clone()),
+            self.link_path.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Link {
+            source: self.source.clone(),
+            link_path: self.link_path.clone(),
+            link_kind: self.kind,
+        }
+    }
+}
+
+// The path that leads from the directory `from` to `to`, both absolute
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, _source: &Path, link_path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link_path)
+}
+
+// Windows needs to know up front whether the link points at a directory
+#[cfg(windows)]
+fn symlink(target: &Path, source: &Path, link_path: &Path) -> io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+
+    if source.is_dir() {
+        symlink_dir(target, link_path)
+    } else {
+        symlink_file(target, link_path)
+    }
+}
+
+// Directory symlinks on Windows have to be removed like directories
+#[cfg(windows)]
+fn remove_link(link_path: &Path) -> io::Result<()> {
+    fs::remove_file(link_path).or_else(|_| fs::remove_dir(link_path))
+}
+
+#[cfg(not(windows))]
+fn remove_link(link_path: &Path) -> io::Result<()> {
+    fs::remove_file(link_path)
+}
@@ -1,80 +1,593 @@
+use crate::blink::config::behaviour::Behavior;
+use crate::blink::operations::archive::ArchiveFormat;
+use crate::blink::operations::batch_operation::BatchOperation;
 use crate::blink::operations::copy_file_operation::CopyFile;
+use crate::blink::operations::create_archive_operation::CreateArchive;
 use crate::blink::operations::create_file_operation::CreateFile;
 use crate::blink::operations::delete_file_operation::DeleteFile;
+use crate::blink::operations::dry_run::{PlannedAction, PlannedJob};
+use crate::blink::operations::extract_archive_operation::ExtractArchive;
+use crate::blink::operations::jobs::{FinishedJob, Job, JobItem, JobReport};
+use crate::blink::operations::journal::{Journal, JournalAction, OperationRecord};
+use crate::blink::operations::link_operation::{CreateLink, LinkKind};
+use crate::blink::operations::permissions_operation::{ChangePermissions, PermissionChange};
+use crate::blink::operations::progress::{Progress, Workload};
+use crate::blink::operations::purge_files_operation::PurgeFiles;
 use crate::blink::operations::rename_file_operation::RenameFile;
-use crate::blink::trash_manager::TrashManager;
+use crate::blink::operations::transfer::CopyOptions;
+use crate::blink::trash_manager::{TrashItem, TrashManager};
+use std::collections::{HashSet, VecDeque};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+pub mod archive;
+pub mod batch_operation;
 pub mod copy_file_operation;
+pub mod create_archive_operation;
 pub mod create_file_operation;
 pub mod delete_file_operation;
+pub mod dry_run;
+pub mod extract_archive_operation;
+pub mod jobs;
+pub mod journal;
+pub mod link_operation;
+pub mod permissions_operation;
+pub mod progress;
+pub mod purge_files_operation;
 pub mod rename_file_operation;
+pub mod rename_pattern;
+pub mod rename_plan;
+pub mod transfer;
 
-pub trait Operation: std::fmt::Debug {
-    fn execute(&mut self) -> io::Result<()>;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Overwrite,
+    Skip,
+    KeepBoth,
+}
+
+// What a paste does with the yanked paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteMode {
+    Copy,
+    Move,
+    Link(LinkKind),
+}
+
+// A single yanked path headed for the cwd. Items whose target already exists need a
+// resolution before the paste can go ahead
+#[derive(Debug, Clone)]
+pub struct PasteItem {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub resolution: Option<ConflictResolution>,
+}
+
+impl PasteItem {
+    pub fn needs_resolution(&self) -> bool {
+        self.resolution.is_none() && self.target.exists()
+    }
+}
+
+pub trait Operation: std::fmt::Debug + Send {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()>;
     fn undo(&self) -> io::Result<()>;
+    fn redo(&mut self) -> io::Result<()>;
+    fn record(&self) -> OperationRecord;
+
+    // The changes execute would make, for the dry-run preview. Must not touch the filesystem
+    fn plan(&self) -> Vec<PlannedAction>;
+
+    // What execute is going to chew through, so background jobs can show a percentage
+    fn workload(&self) -> Workload {
+        Workload { bytes: 0, files: 1 }
+    }
+
+    // Operations that can't be undone are journaled but kept off the undo stack
+    fn undoable(&self) -> bool {
+        true
+    }
 }
 
 pub struct OperationManager {
     history: Vec<Box<dyn Operation>>,
+    redo_stack: Vec<Box<dyn Operation>>,
     max_history: usize,
+    copy_options: CopyOptions,
     trash_manager: TrashManager,
+    pub journal: Journal,
+    running_job: Option<Job>,
+    pending_jobs: VecDeque<(String, Vec<JobItem>)>,
+    job_reports: Vec<JobReport>,
+    journal_warning: Option<String>,
 }
 
+// How many finished jobs the jobs panel remembers
+const MAX_JOB_REPORTS: usize = 50;
+
 impl OperationManager {
-    pub fn new(max_history: usize) -> io::Result<Self> {
+    // Picks the undo/redo stacks back up from the journal of the previous run
+    pub fn new(behavior: &Behavior) -> io::Result<Self> {
+        let max_history = behavior.max_history;
+        let copy_options = CopyOptions {
+            follow_symlinks: behavior.follow_symlinks,
+            verify: behavior.verify_copies,
+        };
+        let trash_manager = TrashManager::new()?;
+        let (journal, journal_warning) = Journal::load()?;
+
+        let skip = journal.history.len().saturating_sub(max_history);
+        let history = journal.history[skip..]
+            .iter()
+            .map(|record| record.clone().into_operation(&trash_manager))
+            .collect();
+        let redo_stack = journal
+            .redo_stack
+            .iter()
+            .map(|record| record.clone().into_operation(&trash_manager))
+            .collect();
+
         Ok(Self {
-            history: Vec::new(),
+            history,
+            redo_stack,
             max_history,
-            trash_manager: TrashManager::new()?,
+            copy_options,
+            trash_manager,
+            journal,
+            running_job: None,
+            pending_jobs: VecDeque::new(),
+            job_reports: Vec::new(),
+            journal_warning,
+        })
+    }
+
+    // Set when the journal couldn't be loaded and blink started with an empty one
+    pub fn take_journal_warning(&mut self) -> Option<String> {
+        self.journal_warning.take()
+    }
+
+    fn save_journal(&mut self, action: JournalAction, record: OperationRecord) -> io::Result<()> {
+        self.journal.record(action, record);
+        self.journal.history = self.history.iter().map(|op| op.record()).collect();
+        self.journal.redo_stack = self.redo_stack.iter().map(|op| op.record()).collect();
+        self.journal.save().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Could not save the operation journal: {}", e),
+            )
         })
     }
 
     fn execute(&mut self, mut op: Box<dyn Operation>) -> io::Result<()> {
-        op.execute()?;
+        op.execute(&Progress::default())?;
+        self.push_history(op)
+    }
 
-        self.history.push(op);
+    fn push_history(&mut self, op: Box<dyn Operation>) -> io::Result<()> {
+        let record = op.record();
+        // A new operation branches off the undone ones, so they can't be redone anymore. That
+        // holds for ones that can't be undone too, they still change what's on disk
+        self.redo_stack.clear();
+        if op.undoable() {
+            self.history.push(op);
+            self.trim_history();
+        }
+
+        self.save_journal(JournalAction::Execute, record)
+    }
 
+    fn trim_history(&mut self) {
         if self.history.len() > self.max_history {
-            self.history.remove(0);
+            let excess = self.history.len() - self.max_history;
+            self.history.drain(..excess);
         }
-
-        Ok(())
     }
 
+    // An operation that fails to undo or redo stays where it was, so the stacks keep matching
+    // the journal
     pub fn undo(&mut self) -> io::Result<()> {
+        self.ensure_no_jobs()?;
         if let Some(op) = self.history.pop() {
-            op.undo()?;
-            Ok(())
+            if let Err(e) = op.undo() {
+                self.history.push(op);
+                return Err(e);
+            }
+            let record = op.record();
+            self.redo_stack.push(op);
+            self.save_journal(JournalAction::Undo, record)
         } else {
             Err(io::Error::new(io::ErrorKind::Other, "Nothing to undo"))
         }
     }
 
-    /*
-    fn empty_trash(&self) -> io::Result<()> {
+    pub fn redo(&mut self) -> io::Result<()> {
+        self.ensure_no_jobs()?;
+        if let Some(mut op) = self.redo_stack.pop() {
+            if let Err(e) = op.redo() {
+                self.redo_stack.push(op);
+                return Err(e);
+            }
+            let record = op.record();
+            self.history.push(op);
+            self.trim_history();
+            self.save_journal(JournalAction::Redo, record)
+        } else {
+            Err(io::Error::other("Nothing to redo"))
+        }
+    }
+
+    pub fn trash_items(&self) -> Vec<TrashItem> {
+        let mut items = self.trash_manager.list();
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+        items
+    }
+
+    // Trash browser actions happen outside the history, the entry is back or gone for good
+    pub fn restore_from_trash(&self, item: &TrashItem, target: &Path) -> io::Result<()> {
+        self.ensure_no_jobs()?;
+        self.trash_manager
+            .restore_from_trash(&item.trash_path, target)
+    }
+
+    pub fn purge_from_trash(&self, item: &TrashItem) -> io::Result<()> {
+        self.ensure_no_jobs()?;
+        self.trash_manager.purge(&item.trash_path)
+    }
+
+    pub fn empty_trash(&self) -> io::Result<()> {
+        self.ensure_no_jobs()?;
         self.trash_manager.empty_trash()
     }
-    */
 
     pub fn create_file(&mut self, path: PathBuf) -> io::Result<()> {
         let op = Box::new(CreateFile::new(path));
         self.execute(op)
     }
 
-    pub fn delete_file(&mut self, path: PathBuf) -> io::Result<()> {
-        let op = Box::new(DeleteFile::new(path, self.trash_manager.clone()));
-        self.execute(op)
+    // One item for the whole selection, so a path that can't be trashed brings the rest back
+    pub fn plan_delete(&self, paths: Vec<PathBuf>) -> PlannedJob {
+        let description = describe_items("Deleting", &paths);
+        let ops: Vec<Box<dyn Operation>> = paths
+            .into_iter()
+            .map(|path| {
+                Box::new(DeleteFile::new(path, self.trash_manager.clone())) as Box<dyn Operation>
+            })
+            .collect();
+        PlannedJob {
+            items: batch_item(&description, ops),
+            description,
+            background: true,
+        }
+    }
+
+    pub fn plan_purge(&self, paths: Vec<PathBuf>) -> PlannedJob {
+        let description = describe_items("Permanently deleting", &paths);
+        // One item, so the journal keeps a single purge entry for the whole selection
+        let item = JobItem::new(description.clone(), Box::new(PurgeFiles::new(paths)));
+        PlannedJob {
+            description,
+            items: vec![item],
+            background: true,
+        }
+    }
+
+    pub fn change_permissions(
+        &mut self,
+        paths: Vec<PathBuf>,
+        change: PermissionChange,
+        recursive: bool,
+    ) {
+        let description = describe_items("Changing permissions of", &paths);
+        let items = paths
+            .into_iter()
+            .map(|path| {
+                let op = Box::new(ChangePermissions::new(
+                    vec![path.clone()],
+                    change,
+                    recursive,
+                ));
+                JobItem::for_path(&path, op)
+            })
+            .collect();
+        self.queue_job(description, items);
+    }
+
+    pub fn create_archive(
+        &mut self,
+        sources: Vec<PathBuf>,
+        archive_path: PathBuf,
+        format: ArchiveFormat,
+    ) {
+        let description = describe_items("Compressing", &sources);
+        let item = JobItem::for_path(
+            &archive_path,
+            Box::new(CreateArchive::new(sources, archive_path.clone(), format)),
+        );
+        self.queue_job(description, vec![item]);
+    }
+
+    // Unpacks the whole archive, or only the given members when there are any
+    pub fn extract_archive(
+        &mut self,
+        archive_path: PathBuf,
+        members: Vec<PathBuf>,
+        destination: PathBuf,
+    ) {
+        let description = if members.is_empty() {
+            describe_items("Extracting", std::slice::from_ref(&archive_path))
+        } else {
+            describe_items("Extracting", &members)
+        };
+        let item = JobItem::for_path(
+            &archive_path,
+            Box::new(ExtractArchive::new(
+                archive_path.clone(),
+                members,
+                destination,
+            )),
+        );
+        self.queue_job(description, vec![item]);
     }
 
     pub fn rename_file(&mut self, old_path: PathBuf, new_path: PathBuf) -> io::Result<()> {
-        let op = Box::new(RenameFile::new(old_path, new_path));
+        let op = Box::new(RenameFile::new(old_path, new_path, false));
         self.execute(op)
     }
 
-    pub fn copy_file(&mut self, old_path: PathBuf, dst_path: PathBuf) -> io::Result<()> {
-        let op = Box::new(CopyFile::new(old_path, dst_path));
-        self.execute(op)
+    // Renames several entries as one history entry, in an order that never overwrites anything
+    pub fn plan_renames(&self, renames: Vec<(PathBuf, PathBuf)>) -> io::Result<PlannedJob> {
+        self.ensure_no_jobs()?;
+        let changed = renames.iter().filter(|(a, b)| a != b).count();
+        let items = rename_plan::plan_renames(renames)?
+            .into_iter()
+            .map(|(old_path, new_path)| {
+                let op = Box::new(RenameFile::new(old_path.clone(), new_path, false));
+                JobItem::for_path(&old_path, op)
+            })
+            .collect();
+
+        Ok(PlannedJob {
+            description: format!("Renaming {} item(s)", changed),
+            items,
+            background: false,
+        })
+    }
+
+    // Copies, moves or links every item that isn't skipped. Overwritten targets are trashed
+    // along with the item so undoing the paste brings them back. Yanked paths that share a name
+    // would land on the same target, later ones keep both instead of replacing an earlier one.
+    // The whole paste is a single item, if one path fails the ones before it are rolled back
+    pub fn plan_paste(&self, items: Vec<PasteItem>, mode: PasteMode) -> PlannedJob {
+        let items: Vec<PasteItem> = items
+            .into_iter()
+            .filter(|item| item.resolution != Some(ConflictResolution::Skip))
+            .collect();
+        let sources: Vec<PathBuf> = items.iter().map(|item| item.source.clone()).collect();
+        let verb = match mode {
+            PasteMode::Copy => "Copying",
+            PasteMode::Move => "Moving",
+            PasteMode::Link(_) => "Linking",
+        };
+        let description = describe_items(verb, &sources);
+
+        let mut pastes = Vec::new();
+        let mut claimed = HashSet::new();
+        for item in items {
+            let mut ops: Vec<Box<dyn Operation>> = Vec::new();
+            let target = match item.resolution {
+                _ if claimed.contains(&item.target) => {
+                    transfer::unique_path(&item.target, &claimed)
+                }
+                Some(ConflictResolution::Overwrite) => {
+                    ops.push(Box::new(DeleteFile::new(
+                        item.target.clone(),
+                        self.trash_manager.clone(),
+                    )));
+                    item.target
+                }
+                Some(ConflictResolution::KeepBoth) => transfer::unique_path(&item.target, &claimed),
+                _ => item.target,
+            };
+            claimed.insert(target.clone());
+
+            ops.push(match mode {
+                PasteMode::Copy => Box::new(CopyFile::new(item.source, target, self.copy_options)),
+                PasteMode::Move => Box::new(RenameFile::new(
+                    item.source,
+                    target,
+                    self.copy_options.verify,
+                )),
+                PasteMode::Link(kind) => Box::new(CreateLink::new(item.source, target, kind)),
+            });
+            pastes.push(combine(ops));
+        }
+        PlannedJob {
+            items: batch_item(&description, pastes),
+            description,
+            background: true,
+        }
+    }
+
+    // Starts a planned job, background ones are queued and the rest run right away
+    pub fn run(&mut self, job: PlannedJob) -> io::Result<()> {
+        if job.background {
+            self.queue_job(job.description, job.items);
+            return Ok(());
+        }
+
+        self.ensure_no_jobs()?;
+        let ops: Vec<Box<dyn Operation>> =
+            job.items.into_iter().map(JobItem::into_operation).collect();
+        if ops.is_empty() {
+            return Ok(());
+        }
+        self.execute(combine(ops))
+    }
+}
+
+// Background jobs
+impl OperationManager {
+    // Queues the items as one job. Jobs run one at a time so they land in the history in the
+    // order they were started
+    fn queue_job(&mut self, description: String, items: Vec<JobItem>) {
+        if items.is_empty() {
+            return;
+        }
+        self.pending_jobs.push_back((description, items));
+        self.start_next_job();
+    }
+
+    fn start_next_job(&mut self) {
+        if self.running_job.is_none()
+            && let Some((description, items)) = self.pending_jobs.pop_front()
+        {
+            self.running_job = Some(Job::spawn(description, items));
+        }
+    }
+
+    pub fn running_job(&self) -> Option<&Job> {
+        self.running_job.as_ref()
+    }
+
+    pub fn pending_job_count(&self) -> usize {
+        self.pending_jobs.len()
+    }
+
+    // The running job and the queued ones
+    pub fn job_count(&self) -> usize {
+        usize::from(self.running_job.is_some()) + self.pending_jobs.len()
+    }
+
+    pub fn pending_jobs(&self) -> impl Iterator<Item = &str> {
+        self.pending_jobs
+            .iter()
+            .map(|(description, _)| description.as_str())
+    }
+
+    // Finished jobs, oldest first
+    pub fn job_reports(&self) -> &[JobReport] {
+        &self.job_reports
+    }
+
+    // Collects the job that finished since the last poll. The items that went through become
+    // one history entry, the rest stay in the job's report until they're retried
+    pub fn poll_jobs(&mut self) -> Vec<FinishedJob> {
+        let mut finished = Vec::new();
+
+        if self
+            .running_job
+            .as_ref()
+            .is_some_and(|job| job.is_finished())
+        {
+            if let Some(job) = self.running_job.take() {
+                let description = job.description.clone();
+                let result = job.join().and_then(|results| {
+                    let (report, succeeded) = JobReport::new(description.clone(), results);
+                    let saved = if succeeded.is_empty() {
+                        Ok(())
+                    } else {
+                        self.push_history(combine(succeeded))
+                    };
+                    let result = report.result().and(saved);
+                    self.add_job_report(report);
+                    result
+                });
+                finished.push(FinishedJob {
+                    description,
+                    result,
+                });
+            }
+            self.start_next_job();
+        }
+
+        finished
+    }
+
+    fn add_job_report(&mut self, report: JobReport) {
+        self.job_reports.push(report);
+        if self.job_reports.len() > MAX_JOB_REPORTS {
+            self.job_reports.remove(0);
+        }
+    }
+
+    // Queues the failed and cancelled items of a finished job again
+    pub fn retry_job(&mut self, index: usize) -> bool {
+        let Some(report) = self.job_reports.get_mut(index) else {
+            return false;
+        };
+        let items = report.take_failed();
+        if items.is_empty() {
+            return false;
+        }
+
+        let report = self.job_reports.remove(index);
+        self.queue_job(report.description, items);
+        true
+    }
+
+    pub fn dismiss_job_report(&mut self, index: usize) {
+        if index < self.job_reports.len() {
+            self.job_reports.remove(index);
+        }
+    }
+
+    // Drops a job that hasn't started yet
+    pub fn cancel_pending_job(&mut self, index: usize) -> bool {
+        self.pending_jobs.remove(index).is_some()
+    }
+
+    pub fn cancel_running_job(&self) -> bool {
+        match &self.running_job {
+            Some(job) => {
+                job.progress.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Cancels the running job and drops the queued ones, returns how many were affected
+    pub fn cancel_jobs(&mut self) -> usize {
+        let mut cancelled = self.pending_jobs.len();
+        self.pending_jobs.clear();
+        if self.cancel_running_job() {
+            cancelled += 1;
+        }
+        cancelled
+    }
+
+    fn ensure_no_jobs(&self) -> io::Result<()> {
+        if self.running_job.is_some() || !self.pending_jobs.is_empty() {
+            Err(io::Error::other("Wait for running jobs to finish"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Several operations become one history entry, a lone operation is recorded as is
+fn combine(mut ops: Vec<Box<dyn Operation>>) -> Box<dyn Operation> {
+    match ops.len() {
+        1 => ops.remove(0),
+        _ => Box::new(BatchOperation::new(ops)),
+    }
+}
+
+// Runs the operations as one job item, they all go through or none of them do
+fn batch_item(description: &str, ops: Vec<Box<dyn Operation>>) -> Vec<JobItem> {
+    if ops.is_empty() {
+        return Vec::new();
+    }
+    vec![JobItem::new(description.to_string(), combine(ops))]
+}
+
+fn describe_items(verb: &str, paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => format!(
+            "{} {}",
+            verb,
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        _ => format!("{} {} items", verb, paths.len()),
     }
 }
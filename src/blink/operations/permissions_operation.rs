@@ -0,0 +1,241 @@
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// What to set on every path, None leaves that part alone. Paths in a selection rarely share a
+// mode, so set and clear only touch the bits that were edited and keep the rest of each mode
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PermissionChange {
+    pub mode: Option<u32>,
+    #[serde(default)]
+    pub set: u32,
+    #[serde(default)]
+    pub clear: u32,
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+}
+
+impl PermissionChange {
+    pub fn changes_mode(&self) -> bool {
+        self.mode.is_some() || self.set != 0 || self.clear != 0
+    }
+
+    // The mode a path with the given one ends up with
+    pub fn new_mode(&self, current: u32) -> u32 {
+        self.mode.unwrap_or((current & !self.clear) | self.set)
+    }
+}
+
+// Mode and ownership of a path before it was changed, so undo can put them back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionState {
+    pub path: PathBuf,
+    pub mode: u32,
+    pub owner: u32,
+    pub group: u32,
+}
+
+#[derive(Debug)]
+pub struct ChangePermissions {
+    paths: Vec<PathBuf>,
+    change: PermissionChange,
+    recursive: bool,
+    previous: Vec<PermissionState>,
+}
+
+impl ChangePermissions {
+    pub fn new(paths: Vec<PathBuf>, change: PermissionChange, recursive: bool) -> Self {
+        Self {
+            paths,
+            change,
+            recursive,
+            previous: Vec::new(),
+        }
+    }
+
+    pub fn restore(
+        paths: Vec<PathBuf>,
+        change: PermissionChange,
+        recursive: bool,
+        previous: Vec<PermissionState>,
+    ) -> Self {
+        Self {
+            paths,
+            change,
+            recursive,
+            previous,
+        }
+    }
+
+    // Children come before their directory, so a mode that takes away access to a directory
+    // doesn't stop the rest of the tree from being changed
+    fn targets(&self) -> io::Result<Vec<PathBuf>> {
+        let mut targets = Vec::new();
+        for path in &self.paths {
+            if self.recursive {
+                collect_tree(path, &mut targets)?;
+            } else {
+                targets.push(path.clone());
+            }
+        }
+        Ok(targets)
+    }
+
+    fn rollback(&self, cause: io::Error) -> io::Error {
+        match self.undo() {
+            Ok(_) => cause,
+            Err(e) => io::Error::new(cause.kind(), format!("{} (rollback failed: {})", cause, e)),
+        }
+    }
+}
+
+impl Operation for ChangePermissions {
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        self.previous.clear();
+        for path in self.targets()? {
+            let status = progress.check_cancelled().and_then(|_| {
+                self.previous.push(read_state(&path)?);
+                apply(&path, self.change)
+            });
+            if let Err(e) = status {
+                return Err(self.rollback(e));
+            }
+            progress.add_files(1);
+        }
+        Ok(())
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        for state in self.previous.iter().rev() {
+            let change = PermissionChange {
+                mode: Some(state.mode),
+                owner: self.change.owner.map(|_| state.owner),
+                group: self.change.group.map(|_| state.group),
+                ..PermissionChange::default()
+            };
+            apply(&state.path, change)?;
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        if !self.previous.is_empty() {
+            self.execute(&Progress::default())?;
+        }
+        Ok(())
+    }
+
+    fn workload(&self) -> Workload {
+        let files = if self.recursive {
+            self.paths.iter().map(|path| count_tree(path)).sum()
+        } else {
+            self.paths.len() as u64
+        };
+        Workload { bytes: 0, files }
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        self.paths
+            .iter()
+            .map(|path| PlannedAction::new(ActionKind::Change, None, path.clone()))
+            .collect()
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Permissions {
+            paths: self.paths.clone(),
+            change: self.change,
+            recursive: self.recursive,
+            previous: self.previous.clone(),
+        }
+    }
+}
+
+// Symlinks inside the tree are skipped, changing them would change whatever they point at
+fn collect_tree(path: &Path, targets: &mut Vec<PathBuf>) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_symlink() {
+                collect_tree(&entry.path(), targets)?;
+            }
+        }
+    }
+    targets.push(path.to_path_buf());
+    Ok(())
+}
+
+fn count_tree(path: &Path) -> u64 {
+    let children = match fs::symlink_metadata(path) {
+        Ok(m) if m.is_dir() => fs::read_dir(path)
+            .map(|read_dir| {
+                read_dir
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_ok_and(|t| !t.is_symlink()))
+                    .map(|entry| count_tree(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or_default(),
+        _ => 0,
+    };
+    children + 1
+}
+
+#[cfg(unix)]
+fn read_state(path: &Path) -> io::Result<PermissionState> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path)?;
+    Ok(PermissionState {
+        path: path.to_path_buf(),
+        mode: metadata.mode() & 0o7777,
+        owner: metadata.uid(),
+        group: metadata.gid(),
+    })
+}
+
+// Ownership goes first since chown clears the setuid and setgid bits, the mode is read before
+// that so those bits survive
+#[cfg(unix)]
+fn apply(path: &Path, change: PermissionChange) -> io::Result<()> {
+    use std::os::unix::fs::{PermissionsExt, chown};
+
+    let mode = if change.changes_mode() {
+        let current = fs::metadata(path)?.permissions().mode() & 0o7777;
+        Some(change.new_mode(current))
+    } else {
+        None
+    };
+    if change.owner.is_some() || change.group.is_some() {
+        chown(path, change.owner, change.group)?;
+    }
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn read_state(_path: &Path) -> io::Result<PermissionState> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Permissions can only be changed on Unix",
+    ))
+}
+
+#[cfg(not(unix))]
+fn apply(_path: &Path, _change: PermissionChange) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Permissions can only be changed on Unix",
+    ))
+}
@@ -0,0 +1,91 @@
+use std::{
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+// How much work an operation is going to do, used as the total of its job progress
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Workload {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl std::ops::Add for Workload {
+    type Output = Workload;
+
+    fn add(self, other: Workload) -> Workload {
+        Workload {
+            bytes: self.bytes + other.bytes,
+            files: self.files + other.files,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    bytes_done: AtomicU64,
+    bytes_total: AtomicU64,
+    files_done: AtomicU64,
+    files_total: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+// Shared between a background job and the UI thread. The job bumps the counters as it goes
+// and checks the cancel flag between chunks, the UI reads them to draw the status bar
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    counters: Arc<Counters>,
+}
+
+impl Progress {
+    pub fn set_total(&self, workload: Workload) {
+        self.counters
+            .bytes_total
+            .store(workload.bytes, Ordering::Relaxed);
+        self.counters
+            .files_total
+            .store(workload.files, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.counters.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn add_files(&self, files: u64) {
+        self.counters.files_done.fetch_add(files, Ordering::Relaxed);
+    }
+
+    pub fn done(&self) -> Workload {
+        Workload {
+            bytes: self.counters.bytes_done.load(Ordering::Relaxed),
+            files: self.counters.files_done.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn total(&self) -> Workload {
+        Workload {
+            bytes: self.counters.bytes_total.load(Ordering::Relaxed),
+            files: self.counters.files_total.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.counters.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.counters.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Bails out of a running operation once the job has been cancelled
+    pub fn check_cancelled(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "Cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
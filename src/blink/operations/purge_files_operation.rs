@@ -0,0 +1,66 @@
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+    transfer,
+};
+use std::{fs, io, path::PathBuf};
+
+// Deletes paths for good without going through the trash. It's journaled so the history
+// browser shows it, but never lands on the undo stack
+#[derive(Debug)]
+pub struct PurgeFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl PurgeFiles {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self { paths }
+    }
+}
+
+impl Operation for PurgeFiles {
+    // Paths that are already gone were removed by an earlier try, so a retry picks up the rest
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        for path in &self.paths {
+            match fs::symlink_metadata(path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                _ => transfer::remove_recursively(path, progress)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        Err(io::Error::other("Permanent deletes can't be undone"))
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        Err(io::Error::other("Permanent deletes can't be redone"))
+    }
+
+    fn workload(&self) -> Workload {
+        self.paths
+            .iter()
+            .map(|path| transfer::measure(path))
+            .fold(Workload::default(), |acc, w| acc + w)
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        self.paths
+            .iter()
+            .map(|path| PlannedAction::new(ActionKind::Delete, None, path.clone()))
+            .collect()
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Purge {
+            paths: self.paths.clone(),
+        }
+    }
+
+    fn undoable(&self) -> bool {
+        false
+    }
+}
@@ -1,34 +1,98 @@
-use crate::blink::operations::Operation;
-use std::{fs, io, path::PathBuf};
+use crate::blink::operations::{
+    Operation,
+    dry_run::{ActionKind, PlannedAction},
+    journal::OperationRecord,
+    progress::{Progress, Workload},
+    transfer::{self, CopyOptions},
+};
+use std::{io, path::PathBuf};
 
 #[derive(Debug)]
 pub struct RenameFile {
     old_path: PathBuf,
     new_path: PathBuf,
+    // Checksums the copy when the move has to go across devices
+    verify: bool,
     executed: bool,
 }
 
 impl RenameFile {
-    pub fn new(old_path: PathBuf, new_path: PathBuf) -> Self {
+    pub fn new(old_path: PathBuf, new_path: PathBuf, verify: bool) -> Self {
         Self {
             old_path,
             new_path,
+            verify,
             executed: false,
         }
     }
+
+    pub fn restore(old_path: PathBuf, new_path: PathBuf, verify: bool) -> Self {
+        Self {
+            old_path,
+            new_path,
+            verify,
+            executed: true,
+        }
+    }
 }
 
 impl Operation for RenameFile {
-    fn execute(&mut self) -> io::Result<()> {
-        fs::rename(&self.old_path, &self.new_path)?;
+    fn execute(&mut self, progress: &Progress) -> io::Result<()> {
+        transfer::move_path(&self.old_path, &self.new_path, self.verify, progress)?;
         self.executed = true;
         Ok(())
     }
 
     fn undo(&self) -> io::Result<()> {
         if self.executed {
-            fs::rename(&self.new_path, &self.old_path)?;
+            transfer::move_path(
+                &self.new_path,
+                &self.old_path,
+                self.verify,
+                &Progress::default(),
+            )?;
         }
         Ok(())
     }
+
+    fn redo(&mut self) -> io::Result<()> {
+        if self.executed {
+            transfer::move_path(
+                &self.old_path,
+                &self.new_path,
+                self.verify,
+                &Progress::default(),
+            )?;
+        }
+        Ok(())
+    }
+
+    // A plain rename is instant, only a move to another device has bytes to copy
+    fn workload(&self) -> Workload {
+        if transfer::same_device(&self.old_path, &self.new_path) {
+            Workload { bytes: 0, files: 1 }
+        } else {
+            CopyOptions {
+                verify: self.verify,
+                ..CopyOptions::default()
+            }
+            .workload(&self.old_path)
+        }
+    }
+
+    fn plan(&self) -> Vec<PlannedAction> {
+        vec![PlannedAction::new(
+            ActionKind::Move,
+            Some(self.old_path.clone()),
+            self.new_path.clone(),
+        )]
+    }
+
+    fn record(&self) -> OperationRecord {
+        OperationRecord::Rename {
+            old_path: self.old_path.clone(),
+            new_path: self.new_path.clone(),
+            verify: self.verify,
+        }
+    }
 }
@@ -0,0 +1,256 @@
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+// A rule that turns each selected name into a new one, either a sed style
+// s/find/replace/flags regex or a template like img_{n:03}{ext}
+pub enum RenamePattern {
+    Regex {
+        regex: Regex,
+        replacement: String,
+        all: bool,
+    },
+    Template(Vec<TemplatePart>),
+}
+
+pub enum TemplatePart {
+    Text(String),
+    Counter { width: usize },
+    Name,
+    Ext,
+}
+
+// One line of the before/after preview. Rows with a problem block the whole rename
+pub struct PreviewRow {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub problem: Option<String>,
+}
+
+impl RenamePattern {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input.strip_prefix("s/") {
+            Some(rest) => parse_regex(rest),
+            None => parse_template(input).map(RenamePattern::Template),
+        }
+    }
+
+    // index is the position in the selection, counters start at 1
+    fn apply(&self, name: &str, index: usize) -> String {
+        match self {
+            RenamePattern::Regex {
+                regex,
+                replacement,
+                all,
+            } => {
+                let limit = if *all { 0 } else { 1 };
+                regex
+                    .replacen(name, limit, replacement.as_str())
+                    .into_owned()
+            }
+            RenamePattern::Template(parts) => {
+                let path = Path::new(name);
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let ext = path
+                    .extension()
+                    .map(|e| format!(".{}", e.to_string_lossy()))
+                    .unwrap_or_default();
+
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        TemplatePart::Text(text) => text.clone(),
+                        TemplatePart::Counter { width } => {
+                            format!("{:0width$}", index + 1, width = *width)
+                        }
+                        TemplatePart::Name => stem.to_string(),
+                        TemplatePart::Ext => ext.clone(),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// Works out the new name of every path and flags the ones that can't be renamed
+pub fn preview(input: &str, paths: &[PathBuf]) -> Result<Vec<PreviewRow>, String> {
+    let pattern = RenamePattern::parse(input)?;
+
+    let mut rows: Vec<PreviewRow> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, source)| {
+            let name = source.file_name().unwrap_or_default().to_string_lossy();
+            let new_name = pattern.apply(&name, i);
+            let problem = if new_name.is_empty() || new_name == "." || new_name == ".." {
+                Some("invalid name".to_string())
+            } else if new_name.contains('/') {
+                Some("contains /".to_string())
+            } else {
+                None
+            };
+            PreviewRow {
+                target: source.with_file_name(new_name),
+                source: source.clone(),
+                problem,
+            }
+        })
+        .collect();
+
+    let sources: HashSet<&PathBuf> = paths.iter().collect();
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for row in &rows {
+        *counts.entry(row.target.clone()).or_default() += 1;
+    }
+    for row in rows.iter_mut().filter(|row| row.problem.is_none()) {
+        if counts[&row.target] > 1 {
+            row.problem = Some("name collision".to_string());
+        } else if !sources.contains(&row.target) && fs::symlink_metadata(&row.target).is_ok() {
+            row.problem = Some("already exists".to_string());
+        }
+    }
+
+    Ok(rows)
+}
+
+// Splits find/replace/flags on the slashes that aren't escaped with a backslash
+fn parse_regex(input: &str) -> Result<RenamePattern, String> {
+    let mut fields = vec![String::new()];
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('/') => fields.last_mut().unwrap().push('/'),
+                Some(next) => {
+                    let field = fields.last_mut().unwrap();
+                    field.push('\\');
+                    field.push(next);
+                }
+                None => fields.last_mut().unwrap().push('\\'),
+            },
+            '/' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    let (find, replacement, flags) = match fields.as_slice() {
+        [find, replacement] => (find, replacement, ""),
+        [find, replacement, flags] => (find, replacement, flags.as_str()),
+        _ => return Err("Expected s/find/replace/flags".to_string()),
+    };
+    if let Some(flag) = flags.chars().find(|c| !"gi".contains(*c)) {
+        return Err(format!("Unknown flag {}", flag));
+    }
+
+    let regex = RegexBuilder::new(find)
+        .case_insensitive(flags.contains('i'))
+        .build()
+        // Regex errors draw a caret under the pattern over several lines, keep the message
+        .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_string())?;
+
+    Ok(RenamePattern::Regex {
+        regex,
+        replacement: replacement.clone(),
+        all: flags.contains('g'),
+    })
+}
+
+fn parse_template(input: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            text.push(c);
+            continue;
+        }
+
+        let mut token = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(c) => token.push(c),
+                None => return Err(format!("Unclosed placeholder {{{}", token)),
+            }
+        }
+        let part = match token.as_str() {
+            "name" => TemplatePart::Name,
+            "ext" => TemplatePart::Ext,
+            "n" => TemplatePart::Counter { width: 0 },
+            _ => match token.strip_prefix("n:").map(|w| w.parse::<usize>()) {
+                Some(Ok(width)) => TemplatePart::Counter { width },
+                _ => return Err(format!("Unknown placeholder {{{}}}", token)),
+            },
+        };
+
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+        }
+        parts.push(part);
+    }
+
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenamePattern, parse_regex, parse_template};
+
+    fn rename(input: &str, name: &str, index: usize) -> String {
+        RenamePattern::parse(input).unwrap().apply(name, index)
+    }
+
+    fn error(result: Result<impl Sized, String>) -> String {
+        result.err().unwrap()
+    }
+
+    #[test]
+    fn templates_fill_in_placeholders() {
+        assert_eq!(rename("img_{n:03}{ext}", "DSC1234.JPG", 0), "img_001.JPG");
+        assert_eq!(rename("{name}-{n}{ext}", "notes.txt", 11), "notes-12.txt");
+        assert_eq!(rename("{name}.bak", "Makefile", 0), "Makefile.bak");
+        assert_eq!(rename("{ext}", "Makefile", 0), "");
+    }
+
+    #[test]
+    fn bad_placeholders_are_errors() {
+        assert_eq!(error(parse_template("a{n")), "Unclosed placeholder {n");
+        assert_eq!(
+            error(parse_template("{size}")),
+            "Unknown placeholder {size}"
+        );
+        assert_eq!(error(parse_template("{n:x}")), "Unknown placeholder {n:x}");
+    }
+
+    #[test]
+    fn regexes_replace_the_first_match_unless_global() {
+        assert_eq!(rename("s/a/o/", "banana", 0), "bonana");
+        assert_eq!(rename("s/a/o/g", "banana", 0), "bonono");
+        assert_eq!(rename("s/A/o/gi", "bAnana", 0), "bonono");
+        assert_eq!(rename(r"s/(\d+)/n$1/", "page12.png", 0), "pagen12.png");
+    }
+
+    #[test]
+    fn escaped_slashes_stay_in_their_field() {
+        assert_eq!(rename(r"s/-/\//", "a-b", 0), "a/b");
+        assert_eq!(rename(r"s/\./_/g", "a.b.c", 0), "a_b_c");
+    }
+
+    #[test]
+    fn bad_regexes_are_errors() {
+        assert_eq!(error(parse_regex("a")), "Expected s/find/replace/flags");
+        assert_eq!(
+            error(parse_regex("a/b/c/d")),
+            "Expected s/find/replace/flags"
+        );
+        assert_eq!(error(parse_regex("a/b/x")), "Unknown flag x");
+        assert!(parse_regex("(/b/").is_err());
+    }
+}
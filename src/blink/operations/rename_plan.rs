@@ -0,0 +1,182 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Turns a set of wanted renames into steps that can run one after another without
+// overwriting anything. Chains like a -> b, b -> c run back to front and cycles like
+// a -> b, b -> a go through a temporary name. Fails when two entries would end up on the same
+// name or a target is taken by something that isn't being renamed
+pub fn plan_renames(renames: Vec<(PathBuf, PathBuf)>) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let renames: Vec<(PathBuf, PathBuf)> = renames.into_iter().filter(|(a, b)| a != b).collect();
+    check_collisions(&renames)?;
+
+    let mut pending: HashMap<PathBuf, PathBuf> = renames.iter().cloned().collect();
+    // Keep the caller's order where possible so the steps read naturally
+    let mut order: Vec<PathBuf> = renames.into_iter().map(|(source, _)| source).collect();
+    let mut steps = Vec::new();
+
+    while !order.is_empty() {
+        let free = order
+            .iter()
+            .position(|source| !pending.contains_key(&pending[source]));
+
+        match free {
+            Some(i) => {
+                let source = order.remove(i);
+                let target = pending.remove(&source).unwrap_or_default();
+                steps.push((source, target));
+            }
+            None => {
+                // Everything left waits on something else, so only cycles remain.
+                // Park one entry on a temporary name to break its cycle
+                let source = order.remove(0);
+                let target = pending.remove(&source).unwrap_or_default();
+                let parked = temporary_path(&source, &pending);
+                steps.push((source, parked.clone()));
+                pending.insert(parked.clone(), target);
+                order.push(parked);
+            }
+        }
+    }
+
+    Ok(steps)
+}
+
+fn check_collisions(renames: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    let sources: HashSet<&PathBuf> = renames.iter().map(|(source, _)| source).collect();
+    let mut targets: HashMap<&PathBuf, &PathBuf> = HashMap::new();
+
+    for (source, target) in renames {
+        if let Some(other) = targets.insert(target, source) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} and {} would both be renamed to {}",
+                    file_name(other),
+                    file_name(source),
+                    file_name(target)
+                ),
+            ));
+        }
+        if !sources.contains(target) && fs::symlink_metadata(target).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", file_name(target)),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn temporary_path(source: &Path, pending: &HashMap<PathBuf, PathBuf>) -> PathBuf {
+    let dir = source.parent().unwrap_or(Path::new(""));
+    let name = file_name(source);
+
+    let mut counter = 1;
+    loop {
+        let candidate = dir.join(format!(".{}.blink-rename{}", name, counter));
+        if !pending.contains_key(&candidate)
+            && !pending.values().any(|target| *target == candidate)
+            && fs::symlink_metadata(&candidate).is_err()
+        {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plan_renames;
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    const DIR: &str = "/nonexistent-blink-rename-plan";
+
+    fn path(name: &str) -> PathBuf {
+        Path::new(DIR).join(name)
+    }
+
+    fn renames(pairs: &[(&str, &str)]) -> Vec<(PathBuf, PathBuf)> {
+        pairs
+            .iter()
+            .map(|(source, target)| (path(source), path(target)))
+            .collect()
+    }
+
+    // Runs the steps over a map of name -> original name, panicking when a step would land on
+    // something that's still there
+    fn run(names: &[&str], steps: &[(PathBuf, PathBuf)]) -> HashMap<PathBuf, PathBuf> {
+        let mut files: HashMap<PathBuf, PathBuf> =
+            names.iter().map(|name| (path(name), path(name))).collect();
+        for (source, target) in steps {
+            assert!(!files.contains_key(target), "{:?} overwrites", target);
+            let original = files.remove(source).expect("source exists");
+            files.insert(target.clone(), original);
+        }
+        files
+    }
+
+    #[test]
+    fn chains_run_back_to_front() {
+        let steps = plan_renames(renames(&[("a", "b"), ("b", "c")])).unwrap();
+        assert_eq!(steps, renames(&[("b", "c"), ("a", "b")]));
+        let files = run(&["a", "b"], &steps);
+        assert_eq!(files[&path("b")], path("a"));
+        assert_eq!(files[&path("c")], path("b"));
+    }
+
+    #[test]
+    fn cycles_go_through_a_temporary_name() {
+        let steps = plan_renames(renames(&[("a", "b"), ("b", "c"), ("c", "a")])).unwrap();
+        assert_eq!(steps.len(), 4);
+        let files = run(&["a", "b", "c"], &steps);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[&path("b")], path("a"));
+        assert_eq!(files[&path("c")], path("b"));
+        assert_eq!(files[&path("a")], path("c"));
+    }
+
+    #[test]
+    fn unchanged_names_are_dropped() {
+        let steps = plan_renames(renames(&[("a", "a"), ("b", "c")])).unwrap();
+        assert_eq!(steps, renames(&[("b", "c")]));
+    }
+
+    #[test]
+    fn two_entries_on_one_name_collide() {
+        let error = plan_renames(renames(&[("a", "c"), ("b", "c")])).unwrap_err();
+        assert_eq!(error.to_string(), "a and b would both be renamed to c");
+    }
+
+    #[test]
+    fn taken_targets_collide() {
+        let dir = std::env::temp_dir().join(format!("blink-rename-plan-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("taken"), "").unwrap();
+
+        let result = plan_renames(vec![(dir.join("a"), dir.join("taken"))]);
+        // Freed up by the rename of that entry itself, so not a collision
+        let freed = plan_renames(vec![
+            (dir.join("a"), dir.join("taken")),
+            (dir.join("taken"), dir.join("b")),
+        ]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result.unwrap_err().to_string(), "taken already exists");
+        assert_eq!(freed.unwrap().len(), 2);
+    }
+}
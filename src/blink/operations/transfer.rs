@@ -0,0 +1,477 @@
+use crate::blink::operations::progress::{Progress, Workload};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopyOptions {
+    // Copy what symlinks point to instead of recreating the links themselves
+    pub follow_symlinks: bool,
+    // Compare checksums of every copied file with its source once the copy is done
+    pub verify: bool,
+}
+
+impl CopyOptions {
+    // Verifying reads the source a second time, that's counted like the copy itself
+    pub fn workload(&self, path: &Path) -> Workload {
+        let workload = measure(path);
+        if self.verify {
+            Workload {
+                bytes: workload.bytes * 2,
+                ..workload
+            }
+        } else {
+            workload
+        }
+    }
+}
+
+// Adds up the size and file count of everything under path. Symlinks count as a file
+// and are never followed so a link loop can't send this around in circles
+pub fn measure(path: &Path) -> Workload {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return Workload::default(),
+    };
+
+    if metadata.file_type().is_symlink() {
+        return Workload { bytes: 0, files: 1 };
+    }
+    if !metadata.is_dir() {
+        return Workload {
+            bytes: metadata.len(),
+            files: 1,
+        };
+    }
+
+    fs::read_dir(path)
+        .map(|read_dir| {
+            read_dir
+                .filter_map(|entry| entry.ok())
+                .map(|entry| measure(&entry.path()))
+                .fold(Workload::default(), |acc, w| acc + w)
+        })
+        .unwrap_or_default()
+}
+
+// Copies files and directories along with their mode bits, timestamps and, where the user is
+// allowed to, their ownership
+pub fn copy_recursively(
+    src: &Path,
+    dst: &Path,
+    options: CopyOptions,
+    progress: &Progress,
+) -> io::Result<()> {
+    copy_entry(src, dst, options, progress, &mut Vec::new())?;
+    if options.verify {
+        verify_checksums(src, dst, progress)?;
+    }
+    Ok(())
+}
+
+// ancestors holds the directories currently being copied, finding one of them again means a
+// symlink points back up the tree
+fn copy_entry(
+    src: &Path,
+    dst: &Path,
+    options: CopyOptions,
+    progress: &Progress,
+    ancestors: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    progress.check_cancelled()?;
+
+    let is_symlink = fs::symlink_metadata(src)?.file_type().is_symlink();
+    if is_symlink && !options.follow_symlinks {
+        return copy_symlink(src, dst, progress);
+    }
+
+    let metadata = fs::metadata(src)?;
+    if metadata.is_dir() {
+        let id = fs::canonicalize(src)?;
+        if ancestors.contains(&id) {
+            if is_symlink {
+                // Recreate the looping link as is rather than copying forever
+                return copy_symlink(src, dst, progress);
+            }
+            return Err(io::Error::other(format!(
+                "Directory cycle at {}",
+                src.display()
+            )));
+        }
+
+        fs::create_dir(dst)?;
+        ancestors.push(id);
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_entry(
+                &entry.path(),
+                &dst.join(entry.file_name()),
+                options,
+                progress,
+                ancestors,
+            )?;
+        }
+        ancestors.pop();
+    } else {
+        copy_file(src, dst, progress)?;
+    }
+
+    // Directory timestamps have to be set after the children are in place
+    copy_metadata(&metadata, dst);
+    Ok(())
+}
+
+// Copies in chunks so the progress moves along and a cancel doesn't wait for a whole file
+fn copy_file(src: &Path, dst: &Path, progress: &Progress) -> io::Result<()> {
+    let mut reader = fs::File::open(src)?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)?;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    loop {
+        progress.check_cancelled()?;
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        progress.add_bytes(read as u64);
+    }
+
+    progress.add_files(1);
+    Ok(())
+}
+
+fn copy_symlink(src: &Path, dst: &Path, progress: &Progress) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, lchown, symlink};
+
+        symlink(&target, dst)?;
+        let metadata = fs::symlink_metadata(src)?;
+        // Ownership is best effort, only root can hand files to other users
+        _ = lchown(dst, Some(metadata.uid()), Some(metadata.gid()));
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::{symlink_dir, symlink_file};
+
+        if src.is_dir() {
+            symlink_dir(&target, dst)?;
+        } else {
+            symlink_file(&target, dst)?;
+        }
+    }
+
+    progress.add_files(1);
+    Ok(())
+}
+
+// Best effort, filesystems like FAT can't store modes or owners and that shouldn't fail a copy
+fn copy_metadata(metadata: &fs::Metadata, dst: &Path) {
+    let mut times = fs::FileTimes::new();
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = metadata.modified() {
+        times = times.set_modified(modified);
+    }
+    if let Ok(file) = fs::File::open(dst) {
+        _ = file.set_times(times);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, chown};
+
+        // Try to keep both owner and group, then just the group which any member may set
+        if chown(dst, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+            _ = chown(dst, None, Some(metadata.gid()));
+        }
+    }
+
+    // Last, since chown clears setuid/setgid bits and a read-only mode would block the above
+    _ = fs::set_permissions(dst, metadata.permissions());
+}
+
+// fs::rename only works within one filesystem. When the destination is on another device
+// the path is copied over, checked against the source and only then removed from the source.
+// Never replaces whatever is at dst, overwriting is done by trashing the target first
+pub fn move_path(src: &Path, dst: &Path, verify: bool, progress: &Progress) -> io::Result<()> {
+    match rename_no_replace(src, dst) {
+        Ok(_) => {
+            progress.add_files(1);
+            Ok(())
+        }
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            move_across_devices(src, dst, verify, progress)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// fs::rename silently replaces an existing file. Where the kernel can refuse that atomically it
+// does, elsewhere the target is checked right before the rename
+fn rename_no_replace(src: &Path, dst: &Path) -> io::Result<()> {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let to_c = |path: &Path| {
+            CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+        };
+        let (src_c, dst_c) = (to_c(src)?, to_c(dst)?);
+        let status = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                src_c.as_ptr(),
+                libc::AT_FDCWD,
+                dst_c.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        if status == 0 {
+            return Ok(());
+        }
+        let e = io::Error::last_os_error();
+        match e.raw_os_error() {
+            // Filesystems without RENAME_NOREPLACE support get the checked rename below
+            Some(libc::EINVAL) | Some(libc::ENOSYS) => {}
+            // A case-only rename on a case-insensitive filesystem finds the file itself
+            Some(libc::EEXIST) if same_file(src, dst) => return fs::rename(src, dst),
+            Some(libc::EEXIST) => return Err(already_exists(dst)),
+            _ => return Err(e),
+        }
+    }
+
+    if fs::symlink_metadata(dst).is_ok() && !same_file(src, dst) {
+        return Err(already_exists(dst));
+    }
+    fs::rename(src, dst)
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn move_across_devices(
+    src: &Path,
+    dst: &Path,
+    verify: bool,
+    progress: &Progress,
+) -> io::Result<()> {
+    if fs::symlink_metadata(dst).is_ok() {
+        return Err(already_exists(dst));
+    }
+
+    let options = CopyOptions {
+        verify,
+        ..CopyOptions::default()
+    };
+    let status = copy_recursively(src, dst, options, progress).and_then(|_| verify_copy(src, dst));
+    if let Err(e) = status {
+        _ = remove_path(dst);
+        return Err(e);
+    }
+
+    remove_path(src)
+}
+
+// Cheap sanity check that everything made it across before the source gets removed
+fn verify_copy(src: &Path, dst: &Path) -> io::Result<()> {
+    let expected = measure(src);
+    let copied = measure(dst);
+    if expected.bytes != copied.bytes || expected.files != copied.files {
+        return Err(io::Error::other(format!(
+            "Copy of {} doesn't match the original",
+            src.display()
+        )));
+    }
+    Ok(())
+}
+
+// Hashes every file in the copy and its source and fails listing the ones that differ.
+// Walks the copy rather than the source, so followed symlinks can't lead it in circles
+fn verify_checksums(src: &Path, dst: &Path, progress: &Progress) -> io::Result<()> {
+    let mut mismatches = Vec::new();
+    collect_mismatches(src, dst, Path::new(""), progress, &mut mismatches)?;
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<String> = mismatches
+        .iter()
+        .map(|path| {
+            if path.as_os_str().is_empty() {
+                dst.display().to_string()
+            } else {
+                path.display().to_string()
+            }
+        })
+        .collect();
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Checksum mismatch for {}", names.join(", ")),
+    ))
+}
+
+fn collect_mismatches(
+    src: &Path,
+    dst: &Path,
+    relative: &Path,
+    progress: &Progress,
+    mismatches: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(dst)?;
+    if metadata.file_type().is_symlink() {
+        if fs::read_link(dst).ok() != fs::read_link(src).ok() {
+            mismatches.push(relative.to_path_buf());
+        }
+    } else if metadata.is_dir() {
+        for entry in fs::read_dir(dst)? {
+            let name = entry?.file_name();
+            collect_mismatches(
+                &src.join(&name),
+                &dst.join(&name),
+                &relative.join(&name),
+                progress,
+                mismatches,
+            )?;
+        }
+    } else {
+        if checksum(src, progress)? != checksum(dst, &Progress::default())? {
+            mismatches.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+// SHA-256 of a file, the bytes read from the source count towards the job's progress
+fn checksum(path: &Path, progress: &Progress) -> io::Result<[u8; 32]> {
+    let mut reader = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    loop {
+        progress.check_cancelled()?;
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        progress.add_bytes(read as u64);
+    }
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(unix)]
+pub fn same_device(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::symlink_metadata(a), existing_ancestor_metadata(b)) {
+        (Ok(a), Some(b)) => a.dev() == b.dev(),
+        _ => true,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_device(_a: &Path, _b: &Path) -> bool {
+    true
+}
+
+// The destination of a move doesn't exist yet, so look at the closest parent that does
+#[cfg(unix)]
+fn existing_ancestor_metadata(path: &Path) -> Option<fs::Metadata> {
+    path.ancestors().find_map(|p| fs::metadata(p).ok())
+}
+
+// Appends _copyN to the file stem until the path is free, used when keeping both files.
+// Paths in claimed are taken by earlier items of the same paste even though they don't exist yet
+pub fn unique_path(path: &Path, claimed: &HashSet<PathBuf>) -> PathBuf {
+    let taken = |path: &Path| path.exists() || claimed.contains(path);
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let candidate = dir.join(format!("{}_copy{}{}", stem, counter, ext));
+        if !taken(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+// Deletes bottom up one entry at a time so large deletes show progress and can be cancelled.
+// Symlinks are removed, never followed
+pub fn remove_recursively(path: &Path, progress: &Progress) -> io::Result<()> {
+    progress.check_cancelled()?;
+
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            remove_recursively(&entry?.path(), progress)?;
+        }
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)?;
+        if metadata.is_file() {
+            progress.add_bytes(metadata.len());
+        }
+        progress.add_files(1);
+        Ok(())
+    }
+}
+
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -1,15 +1,27 @@
 use crate::blink::{
     app::{App, Preview},
+    config::ui::Column,
+    entries::FileEntry,
     file_style::{get_file_color_enhanced, get_file_icon_enhanced},
     logging::Log,
+    operations::{
+        dry_run::PlannedAction,
+        jobs::{Job, JobStatus},
+        journal::JournalAction,
+        rename_pattern::PreviewRow,
+    },
+    states::jobs_state::{JobRow, job_rows},
+    trash_manager::TrashItem,
 };
+use chrono::{DateTime, Local};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Layout, Rect},
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
+use std::time::SystemTime;
 
 pub fn render_app(app: &App, frame: &mut Frame) {
     let outer_layout = Layout::vertical([
@@ -34,7 +46,12 @@ pub fn render_app(app: &App, frame: &mut Frame) {
 }
 
 fn render_current_dir_text(app: &App, frame: &mut Frame, area: Rect) {
-    let dir_text = Paragraph::new(format!(" {}", app.cwd.display()))
+    let read_only = if app.archive.is_some() {
+        " [archive, read-only]"
+    } else {
+        ""
+    };
+    let dir_text = Paragraph::new(format!(" {}{}", app.cwd.display(), read_only))
         .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color()));
     frame.render_widget(Clear, area);
     frame.render_widget(dir_text, area);
@@ -66,15 +83,44 @@ fn render_parent_dir(app: &App, frame: &mut Frame, area: Rect) {
 }
 
 fn render_current_dir(app: &App, frame: &mut Frame, area: Rect) {
-    let width = area.width as usize;
+    // Inside the border
+    let width = area.width.saturating_sub(2) as usize;
+    let columns = &app.config.ui.columns;
+
+    // Every cell is worked out first so each column can be as wide as its widest cell
+    let cells: Vec<Vec<String>> = app
+        .cwd_entries
+        .iter()
+        .map(|entry| {
+            columns
+                .iter()
+                .map(|column| column_cell(app, entry, *column))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
 
     let items: Vec<ListItem> = app
         .cwd_entries
         .iter()
+        .zip(&cells)
         .enumerate()
-        .map(|(idx, entry)| {
+        .map(|(idx, (entry, row))| {
             let icon = get_file_icon_enhanced(entry);
-            let mut style = Style::default().fg(get_file_color_enhanced(entry));
+            let color = if entry.broken_link {
+                app.config.colors.log_error.to_ratatui_color()
+            } else {
+                get_file_color_enhanced(entry)
+            };
+            let mut style = Style::default().fg(color);
 
             if app.visual_mode && app.visual_selection.contains(&idx) {
                 style = style
@@ -82,16 +128,59 @@ fn render_current_dir(app: &App, frame: &mut Frame, area: Rect) {
                     .add_modifier(Modifier::BOLD);
             }
 
-            let size_str = format!("{}", entry.size);
-            let entry_str = format!("{} {}", icon, entry.name);
-            let pad_len = width.saturating_sub(entry_str.len() + size_str.len());
+            let columns_str = row
+                .iter()
+                .zip(columns.iter().zip(&widths))
+                .map(|(cell, (column, width))| match column {
+                    Column::Size | Column::Links => format!(" {:>width$}", cell),
+                    _ => format!(" {:<width$}", cell),
+                })
+                .collect::<String>();
+
+            let mut entry_str = format!("{} {}", icon, entry.name);
+            if let Some(target) = &entry.symlink_target {
+                entry_str.push_str(&format!(" -> {}", target.display()));
+            }
+            let name_start = icon.len() + 1;
+            let mut matched = app.search.as_ref().and_then(|search| {
+                search
+                    .find(&entry.name)
+                    .map(|range| range.start + name_start..range.end + name_start)
+            });
+            // The name gives way to the columns when the pane is narrow
+            let room = width.saturating_sub(columns_str.chars().count());
+            if entry_str.chars().count() > room {
+                let kept = entry_str
+                    .char_indices()
+                    .nth(room.saturating_sub(1))
+                    .map_or(entry_str.len(), |(i, _)| i);
+                entry_str.truncate(kept);
+                entry_str.push('\u{2026}');
+                matched = matched
+                    .filter(|range| range.start < kept)
+                    .map(|range| range.start..range.end.min(kept));
+            }
+            let pad_len = room.saturating_sub(entry_str.chars().count());
             let padding = " ".repeat(pad_len);
 
-            let line = Line::from(vec![
-                Span::styled(entry_str, style),
-                Span::raw(padding),
-                Span::raw(size_str),
-            ]);
+            let mut spans = match matched.filter(|range| !range.is_empty()) {
+                Some(range) => vec![
+                    Span::styled(entry_str[..range.start].to_string(), style),
+                    Span::styled(
+                        entry_str[range.clone()].to_string(),
+                        style.fg(Color::Black).bg(app
+                            .config
+                            .colors
+                            .search_match
+                            .to_ratatui_color()),
+                    ),
+                    Span::styled(entry_str[range.end..].to_string(), style),
+                ],
+                None => vec![Span::styled(entry_str, style)],
+            };
+            spans.push(Span::raw(padding));
+            spans.push(Span::raw(columns_str));
+            let line = Line::from(spans);
 
             ListItem::new(line)
         })
@@ -114,6 +203,33 @@ fn render_current_dir(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_stateful_widget(cwd_list, area, &mut app.list_state.clone());
 }
 
+fn column_cell(app: &App, entry: &FileEntry, column: Column) -> String {
+    match column {
+        Column::Permissions => entry.mode.map(format_mode).unwrap_or_default(),
+        Column::Links => entry
+            .links
+            .map(|links| links.to_string())
+            .unwrap_or_default(),
+        Column::Owner => match (&entry.owner, &entry.group) {
+            (Some(owner), Some(group)) => format!("{} {}", owner, group),
+            (Some(owner), None) => owner.clone(),
+            _ => String::new(),
+        },
+        Column::Size => app
+            .entry_size(entry)
+            .map(format_size)
+            .unwrap_or_else(|| "\u{2026}".to_string()),
+        Column::Date => entry
+            .modified
+            .map(|time| {
+                DateTime::<Local>::from(time)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default(),
+    }
+}
+
 fn render_preview_dir(app: &App, frame: &mut Frame, area: Rect) {
     let preview = if let Some(idx) = app.list_state.selected() {
         if let Some(selected_entry) = app.cwd_entries.get(idx) {
@@ -198,10 +314,61 @@ fn render_status_bar(app: &App, frame: &mut Frame, area: Rect) {
         ""
     };
 
-    let status = format!(
+    let mut status = format!(
         " \u{f07c} {} dirs | \u{f15b} {} files | {} ",
         dir_count, file_count, yank_status
     );
+    if app.dry_run {
+        status.push_str("[Dry run] ");
+    }
+    status.push_str(&format!("| \u{f0dc} {} ", app.sort_order().describe()));
+    if let Some(filter) = app.filter() {
+        status.push_str(&format!("| \u{f0b0} {} ", filter.describe()));
+    }
+    if let Some(archive) = app.opening_archive() {
+        status.push_str(&format!(
+            "| Opening {}\u{2026} ",
+            archive.file_name().unwrap_or_default().to_string_lossy()
+        ));
+    }
+
+    // Directories whose size isn't known yet are left out until it is
+    let sizes: Vec<Option<u64>> = app.cwd_entries.iter().map(|e| app.entry_size(e)).collect();
+    status.push_str(&format!(
+        "| \u{f0a0} {}{} ",
+        format_size(sizes.iter().flatten().sum()),
+        if sizes.contains(&None) {
+            "\u{2026}"
+        } else {
+            ""
+        }
+    ));
+
+    if let Some(job) = app.operation_manager.running_job() {
+        let done = job.progress.done();
+        let total = job.progress.total();
+        // Tar streams can't tell how many files they hold until they've been read through
+        let files = if total.files > 0 {
+            format!("{}/{}", done.files, total.files)
+        } else {
+            done.files.to_string()
+        };
+        status.push_str(&format!("| \u{f0ed} {} {} files", job.description, files));
+        if total.bytes > 0 {
+            status.push_str(&format!(
+                ", {} / {} ({}%)",
+                format_size(done.bytes),
+                format_size(total.bytes),
+                (done.bytes * 100 / total.bytes).min(100)
+            ));
+        }
+        status.push(' ');
+
+        let pending = app.operation_manager.pending_job_count();
+        if pending > 0 {
+            status.push_str(&format!("+{} queued ", pending));
+        }
+    }
 
     let status_bar = Paragraph::new(status)
         .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color()));
@@ -243,6 +410,23 @@ pub fn render_input_prompt_popup(app: &App, frame: &mut Frame, title: String, co
     frame.render_widget(popup, area);
 }
 
+// Larger popup for prompts that need a few lines to explain themselves
+pub fn render_dialog_popup(app: &App, frame: &mut Frame, title: String, lines: Vec<Line>) {
+    let area = centered_rect(50, 40, frame.area());
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::bordered()
+                .title(title)
+                .title_alignment(Alignment::Center)
+                .border_type(app.config.ui.get_border_type())
+                .border_style(Style::default().fg(app.config.colors.border.to_ratatui_color())),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
 pub fn render_bookmark_list(app: &App, frame: &mut Frame, list_state: &mut ListState) {
     let area = centered_rect(60, 60, frame.area());
 
@@ -318,6 +502,390 @@ pub fn render_log_list(app: &App, frame: &mut Frame, list_state: &mut ListState)
     frame.render_stateful_widget(bookmark_list, area, list_state);
 }
 
+pub fn render_history_list(app: &App, frame: &mut Frame, list_state: &mut ListState) {
+    let area = centered_rect(60, 60, frame.area());
+
+    let mut items: Vec<ListItem> = Vec::new();
+    for session in app.operation_manager.journal.sessions.iter().rev() {
+        items.push(ListItem::new(Line::from(Span::styled(
+            format!("\u{f017} Session {}", format_timestamp(session.started)),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))));
+
+        for entry in session.entries.iter().rev() {
+            let (label, color) = match entry.action {
+                JournalAction::Execute => ("Did", app.config.colors.log_info.to_ratatui_color()),
+                JournalAction::Undo => ("Undid", app.config.colors.log_warning.to_ratatui_color()),
+                JournalAction::Redo => ("Redid", app.config.colors.log_info.to_ratatui_color()),
+            };
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!(
+                    "  {} {}: {}",
+                    format_timestamp(entry.timestamp),
+                    label,
+                    entry.operation.describe()
+                ),
+                Style::default().fg(color),
+            ))));
+        }
+    }
+
+    let history_list = List::new(items)
+        .block(
+            Block::bordered()
+                .title("Operation History")
+                .title_alignment(Alignment::Center)
+                .border_type(app.config.ui.get_border_type())
+                .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color())),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(app.config.colors.selected_bg.to_ratatui_color())
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(history_list, area, list_state);
+}
+
+pub fn render_trash_list(
+    app: &App,
+    frame: &mut Frame,
+    items: &[TrashItem],
+    list_state: &mut ListState,
+) {
+    let area = centered_rect(70, 60, frame.area());
+
+    let list_items: Vec<ListItem> = items
+        .iter()
+        .map(|item| {
+            let deleted_at = item
+                .deleted_at
+                .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{}  {:>9}  ",
+                        deleted_at,
+                        item.size()
+                            .map(format_size)
+                            .unwrap_or_else(|| "\u{2026}".to_string())
+                    ),
+                    Style::default().fg(app.config.colors.log_info.to_ratatui_color()),
+                ),
+                Span::raw(item.original_path.to_string_lossy()),
+            ]))
+        })
+        .collect();
+
+    let trash_list = List::new(list_items)
+        .block(
+            Block::bordered()
+                .title("Trash")
+                .title_bottom(
+                    Line::from("enter restore · m restore to · d delete · E empty").centered(),
+                )
+                .title_alignment(Alignment::Center)
+                .border_type(app.config.ui.get_border_type())
+                .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color())),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(app.config.colors.selected_bg.to_ratatui_color())
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(trash_list, area, list_state);
+}
+
+pub fn render_jobs_list(app: &App, frame: &mut Frame, list_state: &mut ListState) {
+    let area = centered_rect(70, 60, frame.area());
+    let manager = &app.operation_manager;
+    let info = Style::default().fg(app.config.colors.log_info.to_ratatui_color());
+    let warning = Style::default().fg(app.config.colors.log_warning.to_ratatui_color());
+    let error = Style::default().fg(app.config.colors.log_error.to_ratatui_color());
+    let pending: Vec<&str> = manager.pending_jobs().collect();
+
+    let items: Vec<ListItem> = job_rows(app)
+        .into_iter()
+        .map(|row| match row {
+            JobRow::Running => {
+                let job = manager.running_job();
+                let progress = job.map(job_progress).unwrap_or_default();
+                let description = job.map(|job| job.description.as_str()).unwrap_or_default();
+                ListItem::new(Line::from(Span::styled(
+                    format!("\u{f0ed} Running    {} {}", description, progress),
+                    info,
+                )))
+            }
+            JobRow::Pending(i) => {
+                ListItem::new(Line::from(format!("\u{f017} Queued     {}", pending[i])))
+            }
+            JobRow::Finished(i) => {
+                let report = &manager.job_reports()[i];
+                let (label, style) = match report.status {
+                    JobStatus::Finished => ("\u{f00c} Finished  ", info),
+                    JobStatus::Cancelled => ("\u{f05e} Cancelled ", warning),
+                    JobStatus::Failed => ("\u{f00d} Failed    ", error),
+                };
+                let mut lines = vec![Line::from(Span::styled(
+                    format!(" {} {}", label, report.description),
+                    style,
+                ))];
+                if !report.failures.is_empty() {
+                    lines[0].push_span(Span::raw(format!(
+                        " ({} of {} items left)",
+                        report.failures.len(),
+                        report.item_count
+                    )));
+                }
+                for failure in &report.failures {
+                    lines.push(Line::from(Span::styled(
+                        format!("      {}: {}", failure.label, failure.error),
+                        style,
+                    )));
+                }
+                ListItem::new(lines)
+            }
+        })
+        .collect();
+
+    let jobs_list = List::new(items)
+        .block(
+            Block::bordered()
+                .title("Jobs")
+                .title_bottom(Line::from("r retry failed · c cancel · d dismiss").centered())
+                .title_alignment(Alignment::Center)
+                .border_type(app.config.ui.get_border_type())
+                .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color())),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(app.config.colors.selected_bg.to_ratatui_color())
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(jobs_list, area, list_state);
+}
+
+// How far along a running job is, by bytes when it knows them and by files otherwise
+fn job_progress(job: &Job) -> String {
+    let done = job.progress.done();
+    let total = job.progress.total();
+    if let Some(percent) = (done.bytes * 100).checked_div(total.bytes) {
+        format!("{}%", percent.min(100))
+    } else if total.files > 0 {
+        format!("{}/{} files", done.files, total.files)
+    } else {
+        format!("{} files", done.files)
+    }
+}
+
+pub fn render_dry_run(
+    app: &App,
+    frame: &mut Frame,
+    description: &str,
+    actions: &[PlannedAction],
+    list_state: &mut ListState,
+) {
+    let area = centered_rect(70, 70, frame.area());
+    let error = Style::default().fg(app.config.colors.log_error.to_ratatui_color());
+    let warning = Style::default().fg(app.config.colors.log_warning.to_ratatui_color());
+    let conflicts = actions
+        .iter()
+        .filter(|action| action.conflict.is_some())
+        .count();
+
+    let items: Vec<ListItem> = actions
+        .iter()
+        .map(|action| {
+            let kind = if action.overwrites {
+                format!("{} (overwrite)", action.kind.describe())
+            } else {
+                action.kind.describe().to_string()
+            };
+            let path = match &action.source {
+                Some(source) => format!("{} -> {}", source.display(), action.target.display()),
+                None => action.target.display().to_string(),
+            };
+            let style = if action.overwrites {
+                warning
+            } else {
+                Style::default()
+            };
+
+            let mut lines = vec![Line::from(vec![
+                Span::styled(format!("{:<18}", kind), style),
+                Span::raw(path),
+            ])];
+            if let Some(conflict) = &action.conflict {
+                lines.push(Line::from(Span::styled(
+                    format!("{:<18}\u{f071} {}", "", conflict),
+                    error,
+                )));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+
+    let summary = match conflicts {
+        0 => format!("{} action(s)", actions.len()),
+        _ => format!("{} action(s), {} conflict(s)", actions.len(), conflicts),
+    };
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(format!("Dry run: {} ({})", description, summary))
+                .title_bottom(Line::from("enter run · esc abort").centered())
+                .title_alignment(Alignment::Center)
+                .border_type(app.config.ui.get_border_type())
+                .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color())),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(app.config.colors.selected_bg.to_ratatui_color())
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, list_state);
+}
+
+pub fn render_rename_preview(
+    app: &App,
+    frame: &mut Frame,
+    input: &str,
+    preview: &Result<Vec<PreviewRow>, String>,
+) {
+    let area = centered_rect(70, 70, frame.area());
+    let layout = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).split(area);
+    let error_style = Style::default().fg(app.config.colors.log_error.to_ratatui_color());
+
+    let prompt = Paragraph::new(format!("Pattern: {}", input)).block(
+        Block::bordered()
+            .title("Pattern rename")
+            .title_alignment(Alignment::Center)
+            .title_bottom(
+                Line::from("s/find/replace/gi or template with {n:03} {name} {ext}").centered(),
+            )
+            .border_type(app.config.ui.get_border_type())
+            .border_style(Style::default().fg(app.config.colors.border.to_ratatui_color())),
+    );
+
+    let (title, items): (Line, Vec<ListItem>) = match preview {
+        Err(e) => (Line::styled(e.clone(), error_style), Vec::new()),
+        Ok(rows) => {
+            let problems = rows.iter().filter(|row| row.problem.is_some()).count();
+            let title = if problems > 0 {
+                Line::styled(
+                    format!("{} problem(s), fix them to rename", problems),
+                    error_style,
+                )
+            } else {
+                let changes = rows.iter().filter(|row| row.source != row.target).count();
+                Line::from(format!("{} rename(s), enter to apply", changes))
+            };
+
+            let items = rows
+                .iter()
+                .map(|row| {
+                    let old = row.source.file_name().unwrap_or_default().to_string_lossy();
+                    let new = row.target.file_name().unwrap_or_default().to_string_lossy();
+                    let line = match &row.problem {
+                        Some(problem) => {
+                            Line::styled(format!("{} -> {} ({})", old, new, problem), error_style)
+                        }
+                        None if row.source == row.target => Line::styled(
+                            format!("{} (unchanged)", old),
+                            Style::default().add_modifier(Modifier::DIM),
+                        ),
+                        None => Line::from(format!("{} -> {}", old, new)),
+                    };
+                    ListItem::new(line)
+                })
+                .collect();
+            (title, items)
+        }
+    };
+
+    let list = List::new(items).block(
+        Block::bordered()
+            .title(title)
+            .title_alignment(Alignment::Center)
+            .border_type(app.config.ui.get_border_type())
+            .style(Style::default().fg(app.config.colors.status_bar.to_ratatui_color())),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(prompt, layout[0]);
+    frame.render_widget(list, layout[1]);
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub fn format_system_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// The type and permission bits the way ls -l shows them, e.g. drwxr-xr-x
+pub fn format_mode(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o010000 => 'p',
+        0o140000 => 's',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        _ => '-',
+    };
+    // Setuid, setgid and sticky take the place of the matching execute bit
+    let triplet = |shift: u32, special: bool, special_char: char| {
+        let read = if mode >> shift & 0o4 != 0 { 'r' } else { '-' };
+        let write = if mode >> shift & 0o2 != 0 { 'w' } else { '-' };
+        let exec = match (mode >> shift & 0o1 != 0, special) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        };
+        [read, write, exec]
+    };
+
+    std::iter::once(kind)
+        .chain(triplet(6, mode & 0o4000 != 0, 's'))
+        .chain(triplet(3, mode & 0o2000 != 0, 's'))
+        .chain(triplet(0, mode & 0o1000 != 0, 't'))
+        .collect()
+}
+
+fn format_timestamp(secs: u64) -> String {
+    DateTime::from_timestamp(secs as i64, 0)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::vertical([
         Constraint::Percentage((100 - percent_y) / 2),
@@ -0,0 +1,264 @@
+use crate::blink::entries::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Created,
+    Extension,
+}
+
+impl SortKey {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+            SortKey::Created => "created",
+            SortKey::Extension => "extension",
+        }
+    }
+}
+
+// How one directory is listed. Ties on the key fall back to the name so the order is stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOrder {
+    #[serde(default)]
+    pub key: SortKey,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default = "default_dirs_first")]
+    pub dirs_first: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            key: SortKey::default(),
+            reverse: false,
+            dirs_first: default_dirs_first(),
+        }
+    }
+}
+
+fn default_dirs_first() -> bool {
+    true
+}
+
+impl SortOrder {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}{}",
+            self.key.describe(),
+            if self.reverse { "\u{2193}" } else { "\u{2191}" },
+            if self.dirs_first { ", dirs first" } else { "" }
+        )
+    }
+
+    // Reversing flips the key order only, directories stay on top when dirs_first is set
+    pub fn apply(&self, entries: &mut [FileEntry]) {
+        self.apply_with_sizes(entries, |entry| Some(entry.size));
+    }
+
+    // Like apply but with sizes from elsewhere, the cwd knows what its directories hold. Sizes
+    // that aren't known yet sort as the smallest
+    pub fn apply_with_sizes(
+        &self,
+        entries: &mut [FileEntry],
+        size: impl Fn(&FileEntry) -> Option<u64>,
+    ) {
+        entries.sort_by(|a, b| {
+            let dirs = if self.dirs_first {
+                b.is_dir.cmp(&a.is_dir)
+            } else {
+                Ordering::Equal
+            };
+            let by_key = match self.key {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Size => size(a).cmp(&size(b)),
+                SortKey::Modified => a.modified.cmp(&b.modified),
+                SortKey::Created => a.created.cmp(&b.created),
+                SortKey::Extension => extension(&a.name).cmp(&extension(&b.name)),
+            };
+            let ordering = by_key.then_with(|| natural_cmp(&a.name, &b.name));
+            dirs.then(if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            })
+        });
+    }
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// Case-insensitive order where runs of digits compare by value, so file2 comes before file10
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let a = a.to_lowercase();
+    let b = b.to_lowercase();
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(&b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a_chars);
+                let y = take_number(&mut b_chars);
+                // Longer runs without leading zeros are bigger numbers, no need to parse them
+                let ordering = x
+                    .trim_start_matches('0')
+                    .len()
+                    .cmp(&y.trim_start_matches('0').len())
+                    .then_with(|| x.trim_start_matches('0').cmp(y.trim_start_matches('0')))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.cmp(y);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+        number.push(c);
+        chars.next();
+    }
+    number
+}
+
+// The sort picked for each directory, kept next to the bookmarks so it survives restarts.
+// Directories without an entry use the default order
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SortSettings {
+    directories: HashMap<PathBuf, SortOrder>,
+}
+
+impl SortSettings {
+    // A settings file that can't be read shouldn't keep blink from starting, the default order is
+    // used instead and the reason comes back as a warning to log
+    pub fn load() -> (Self, Option<String>) {
+        match Self::read() {
+            Ok(settings) => (settings, None),
+            Err(e) => (
+                Self::default(),
+                Some(format!(
+                    "Couldn't load the sort settings, using the default order: {}",
+                    e.to_string().replace('\n', " ")
+                )),
+            ),
+        }
+    }
+
+    fn read() -> color_eyre::Result<Self> {
+        let path = Self::settings_path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        let settings: SortSettings = toml::from_str(&contents)?;
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> color_eyre::Result<()> {
+        let path = Self::settings_path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let toml_string = toml::to_string_pretty(self)?;
+        fs::write(&path, toml_string)?;
+        Ok(())
+    }
+
+    fn settings_path() -> color_eyre::Result<PathBuf> {
+        use directories::ProjectDirs;
+
+        let proj_dirs = ProjectDirs::from("com", "Varcrow", "blink")
+            .ok_or_else(|| color_eyre::eyre::eyre!("Could not determine config directory"))?;
+
+        Ok(proj_dirs.config_dir().join("sorting.toml"))
+    }
+
+    pub fn order_for(&self, dir: &Path) -> SortOrder {
+        self.directories.get(dir).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, dir: PathBuf, order: SortOrder) {
+        if order == SortOrder::default() {
+            self.directories.remove(&dir);
+        } else {
+            self.directories.insert(dir, order);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::natural_cmp;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file2"), Ordering::Greater);
+        assert_eq!(natural_cmp("file10", "file10"), Ordering::Equal);
+        assert_eq!(natural_cmp("9", "10"), Ordering::Less);
+    }
+
+    #[test]
+    fn leading_zeros_only_break_ties() {
+        assert_eq!(natural_cmp("a1", "a01"), Ordering::Less);
+        assert_eq!(natural_cmp("a01", "a1"), Ordering::Greater);
+        assert_eq!(natural_cmp("a01", "a2"), Ordering::Less);
+        assert_eq!(natural_cmp("007", "7"), Ordering::Greater);
+        assert_eq!(natural_cmp("010", "9"), Ordering::Greater);
+        assert_eq!(natural_cmp("000", "0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn mixed_digit_runs() {
+        assert_eq!(natural_cmp("a2b10", "a2b9"), Ordering::Greater);
+        assert_eq!(natural_cmp("x9y100", "x10y2"), Ordering::Less);
+        assert_eq!(natural_cmp("v1.9", "v1.10"), Ordering::Less);
+        assert_eq!(natural_cmp("2024-01-05", "2024-1-5"), Ordering::Greater);
+    }
+
+    #[test]
+    fn letters_ignore_case() {
+        assert_eq!(natural_cmp("File2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("b", "A"), Ordering::Greater);
+        assert_eq!(natural_cmp("abc", "ab"), Ordering::Greater);
+        assert_eq!(natural_cmp("file", "file1"), Ordering::Less);
+    }
+}
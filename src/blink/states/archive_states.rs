@@ -0,0 +1,221 @@
+use crate::blink::{
+    app::App,
+    logging::Log,
+    operations::archive::{self, ArchiveFormat},
+    rendering::{render_app, render_dialog_popup},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, text::Line};
+use std::path::PathBuf;
+
+// Asks for the name of a new archive holding the current entry or the visual selection. The
+// format follows the extension, tab swaps it for the next one
+pub struct CreateArchiveState {
+    input: String,
+    paths: Vec<PathBuf>,
+}
+
+impl CreateArchiveState {
+    pub fn new(app: &App) -> Self {
+        let paths = app.selected_paths();
+        let stem = match paths.as_slice() {
+            [path] => path.file_name(),
+            _ => app.cwd.file_name(),
+        }
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+        Self {
+            input: ArchiveFormat::Zip.apply_to(&stem),
+            paths,
+        }
+    }
+
+    fn close(app: &mut App) -> Box<dyn State> {
+        if app.visual_mode {
+            app.toggle_visual_mode();
+        }
+        Box::new(MainState)
+    }
+}
+
+impl State for CreateArchiveState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["tab".to_string()]) {
+            let format = ArchiveFormat::from_name(&self.input)
+                .map_or(ArchiveFormat::Zip, ArchiveFormat::next);
+            self.input = format.apply_to(&self.input);
+            return self;
+        }
+        if let KeyCode::Char(c) = key {
+            self.input.push(c);
+            return self;
+        }
+        if kb.matches(key, &["backspace".to_string()]) {
+            self.input.pop();
+            return self;
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            let Some(format) = ArchiveFormat::from_name(&self.input) else {
+                app.log_manager.add_log(Log::Error {
+                    message: format!(
+                        "{} needs a .zip, .tar, .tar.gz, .tar.xz or .tar.zst extension",
+                        self.input
+                    ),
+                });
+                return self;
+            };
+            if !self.paths.is_empty() {
+                app.create_archive(self.paths, &self.input, format);
+            }
+            return Self::close(app);
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            return Self::close(app);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let lines = vec![
+            Line::from(match self.paths.as_slice() {
+                [path] => format!(
+                    "Compress {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                _ => format!("Compress {} items", self.paths.len()),
+            }),
+            Line::from(""),
+            Line::from(format!("Name: {}", self.input)),
+            Line::from(""),
+            Line::from("tab next format · enter compress · esc cancel"),
+        ];
+
+        render_dialog_popup(app, frame, "New archive".to_string(), lines);
+    }
+}
+
+// Asks where to unpack the archive under the cursor: a new folder named after it, the current
+// directory, or a typed path
+pub struct ExtractArchiveState {
+    archive: PathBuf,
+    stem: String,
+    prompt: Option<String>,
+}
+
+impl ExtractArchiveState {
+    pub fn start(app: &mut App) -> Box<dyn State> {
+        let Some(archive) = app
+            .list_state
+            .selected()
+            .and_then(|i| app.cwd_entries.get(i))
+            .map(|entry| entry.path.clone())
+        else {
+            return Box::new(MainState);
+        };
+
+        let name = archive
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match archive::stem(&name) {
+            Some(stem) if archive.is_file() => Box::new(Self {
+                stem: stem.to_string(),
+                archive,
+                prompt: None,
+            }),
+            _ => {
+                app.log_manager.add_log(Log::Error {
+                    message: format!("{} isn't an archive blink can extract", name),
+                });
+                Box::new(MainState)
+            }
+        }
+    }
+
+    fn extract(archive: PathBuf, app: &mut App, destination: PathBuf) -> Box<dyn State> {
+        app.extract_archive(archive, destination);
+        Box::new(MainState)
+    }
+}
+
+impl State for ExtractArchiveState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if let Some(input) = &mut self.prompt {
+            if let KeyCode::Char(c) = key {
+                input.push(c);
+            } else if kb.matches(key, &["backspace".to_string()]) {
+                input.pop();
+            } else if kb.matches(key, &["esc".to_string()]) {
+                self.prompt = None;
+            } else if kb.matches(key, &["enter".to_string()]) && !input.trim().is_empty() {
+                let destination = app.cwd.join(input.trim());
+                return Self::extract(self.archive, app, destination);
+            }
+            return self;
+        }
+
+        if kb.matches(key, &["f".to_string()]) {
+            let destination = app.cwd.join(&self.stem);
+            if destination.exists() {
+                app.log_manager.add_log(Log::Error {
+                    message: format!("Failed to extract: {} already exists", self.stem),
+                });
+                return Box::new(MainState);
+            }
+            return Self::extract(self.archive, app, destination);
+        }
+        if kb.matches(key, &["h".to_string()]) {
+            let destination = app.cwd.clone();
+            return Self::extract(self.archive, app, destination);
+        }
+        if kb.matches(key, &["p".to_string()]) {
+            self.prompt = Some(String::new());
+            return self;
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            return Box::new(MainState);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let name = self
+            .archive
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let lines = match &self.prompt {
+            Some(input) => vec![
+                Line::from(format!("Extract {} to", name)),
+                Line::from(""),
+                Line::from(format!("Path: {}", input)),
+                Line::from(""),
+                Line::from("enter extract · esc back"),
+            ],
+            None => vec![
+                Line::from(format!("Extract {}", name)),
+                Line::from(""),
+                Line::from(format!("f: into a new folder {}/", self.stem)),
+                Line::from("h: here"),
+                Line::from("p: to another path"),
+                Line::from(""),
+                Line::from("esc: cancel"),
+            ],
+        };
+
+        render_dialog_popup(app, frame, "Extract archive".to_string(), lines);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::blink::{
+    app::App,
+    operations::progress::Workload,
+    rendering::{format_size, render_app, render_dialog_popup, render_input_prompt_popup},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    style::{Modifier, Style},
+    text::Line,
+};
+use std::sync::{Arc, Mutex};
+
+// Asks before deleting the current selection. Trashing only asks when behavior.confirm_delete
+// is set, permanent deletes always ask and spell out what is about to be lost
+pub struct DeleteState {
+    permanent: bool,
+    count: usize,
+    // Measured in the background for permanent deletes, big trees take a while
+    workload: Arc<Mutex<Option<Workload>>>,
+}
+
+impl DeleteState {
+    pub fn new(app: &mut App, permanent: bool) -> Self {
+        let paths = app.selected_paths();
+        let count = paths.len();
+        let workload = if permanent {
+            app.measure_paths(paths)
+        } else {
+            Arc::new(Mutex::new(None))
+        };
+
+        Self {
+            permanent,
+            count,
+            workload,
+        }
+    }
+
+    // Runs the delete straight away when trashing without confirmation
+    pub fn start(app: &mut App, permanent: bool) -> Box<dyn State> {
+        if app.selected_paths().is_empty() {
+            return Self::finish(app, false, permanent);
+        }
+        if permanent || app.config.behavior.confirm_delete {
+            return Box::new(Self::new(app, permanent));
+        }
+        Self::finish(app, true, false)
+    }
+
+    fn finish(app: &mut App, confirmed: bool, permanent: bool) -> Box<dyn State> {
+        if confirmed {
+            if permanent {
+                app.permanently_delete_current_selection();
+            } else {
+                app.delete_current_selection();
+            }
+        }
+        if app.visual_mode {
+            app.toggle_visual_mode();
+        }
+        Box::new(MainState)
+    }
+}
+
+impl State for DeleteState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string(), "n".to_string()]) {
+            return Self::finish(app, false, self.permanent);
+        }
+        if kb.matches(key, &["enter".to_string(), "y".to_string()]) {
+            return Self::finish(app, true, self.permanent);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        if !self.permanent {
+            render_input_prompt_popup(
+                app,
+                frame,
+                format!("Delete {} item(s)?", self.count),
+                "y / n".to_string(),
+            );
+            return;
+        }
+
+        let warning = Style::default()
+            .fg(app.config.colors.log_error.to_ratatui_color())
+            .add_modifier(Modifier::BOLD);
+        let size = match *self.workload.lock().unwrap() {
+            Some(workload) => format!(
+                "{} in {} file(s)",
+                format_size(workload.bytes),
+                workload.files
+            ),
+            None => "\u{2026}".to_string(),
+        };
+        let lines = vec![
+            Line::styled(format!("{} item(s), {}", self.count, size), warning),
+            Line::from(""),
+            Line::from("These skip the trash and are removed for good."),
+            Line::styled("This cannot be undone.", warning),
+            Line::from(""),
+            Line::from("y / n"),
+        ];
+        render_dialog_popup(app, frame, "Permanently delete?".to_string(), lines);
+    }
+}
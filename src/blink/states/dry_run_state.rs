@@ -0,0 +1,99 @@
+use crate::blink::{
+    app::App,
+    logging::Log,
+    operations::dry_run::{PlannedAction, PlannedJob},
+    rendering::{render_app, render_dry_run},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, widgets::ListState};
+
+// Shows everything a job would do before it runs, enter starts it and esc drops it
+pub struct DryRunState {
+    job: PlannedJob,
+    actions: Vec<PlannedAction>,
+    clears_yank: bool,
+    list_state: ListState,
+}
+
+impl DryRunState {
+    pub fn new(job: PlannedJob, clears_yank: bool) -> Self {
+        let actions = job.preview();
+        Self {
+            job,
+            actions,
+            clears_yank,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+}
+
+impl State for DryRunState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+        let row_count = self.actions.len();
+
+        if kb.matches(key, &["enter".to_string()]) {
+            app.run_job(self.job, self.clears_yank);
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &kb.quit) {
+            app.log_manager.add_log(Log::Info {
+                message: format!("Dry run aborted: {}", self.job.description),
+            });
+            return Box::new(MainState);
+        }
+
+        if kb.matches(key, &kb.move_up) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        row_count - 1
+                    } else {
+                        i - 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        if kb.matches(key, &kb.move_down) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i >= row_count - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_dry_run(
+            app,
+            frame,
+            &self.job.description,
+            &self.actions,
+            &mut self.list_state.clone(),
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use crate::blink::{
+    app::App,
+    rendering::{render_app, render_history_list},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, widgets::ListState};
+
+pub struct HistoryState {
+    pub list_state: ListState,
+}
+
+impl State for HistoryState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+        let row_count = app.operation_manager.journal.row_count();
+
+        if kb.matches(key, &kb.quit) {
+            return Box::new(MainState);
+        }
+
+        if kb.matches(key, &kb.move_up) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        row_count - 1
+                    } else {
+                        i - 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        if kb.matches(key, &kb.move_down) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i >= row_count - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_history_list(app, frame, &mut self.list_state.clone());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::blink::{
+    app::{App, RunningState},
+    logging::Log,
+    rendering::{render_app, render_dialog_popup, render_input_prompt_popup, render_jobs_list},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, text::Line, widgets::ListState};
+
+// A line of the jobs panel, the running job first, then the queue, then finished jobs newest first
+#[derive(Clone, Copy)]
+pub enum JobRow {
+    Running,
+    Pending(usize),
+    Finished(usize),
+}
+
+pub fn job_rows(app: &App) -> Vec<JobRow> {
+    let manager = &app.operation_manager;
+    let mut rows = Vec::new();
+    if manager.running_job().is_some() {
+        rows.push(JobRow::Running);
+    }
+    rows.extend((0..manager.pending_job_count()).map(JobRow::Pending));
+    rows.extend((0..manager.job_reports().len()).rev().map(JobRow::Finished));
+    rows
+}
+
+pub struct JobsState {
+    pub list_state: ListState,
+}
+
+impl JobsState {
+    // Jobs come and go underneath the panel, so the cursor is kept inside the list
+    fn clamp_selection(&mut self, row_count: usize) {
+        if let Some(i) = self.list_state.selected()
+            && i >= row_count
+        {
+            self.list_state.select(row_count.checked_sub(1));
+        }
+    }
+
+    fn selected_row(&self, app: &App) -> Option<JobRow> {
+        self.list_state
+            .selected()
+            .and_then(|i| job_rows(app).get(i).copied())
+    }
+}
+
+impl State for JobsState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+        let row_count = job_rows(app).len();
+        self.clamp_selection(row_count);
+
+        if kb.matches(key, &kb.quit) {
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["r".to_string()]) {
+            if let Some(JobRow::Finished(i)) = self.selected_row(app)
+                && app.operation_manager.retry_job(i)
+            {
+                app.log_manager.add_log(Log::Info {
+                    message: "Retrying the failed items".to_string(),
+                });
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.cancel_jobs) {
+            match self.selected_row(app) {
+                Some(JobRow::Running) => {
+                    app.operation_manager.cancel_running_job();
+                }
+                Some(JobRow::Pending(i)) => {
+                    app.operation_manager.cancel_pending_job(i);
+                }
+                _ => {}
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.delete) {
+            if let Some(JobRow::Finished(i)) = self.selected_row(app) {
+                app.operation_manager.dismiss_job_report(i);
+            }
+            return self;
+        }
+
+        if kb.matches(key, &kb.move_up) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        row_count - 1
+                    } else {
+                        i - 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        if kb.matches(key, &kb.move_down) {
+            if row_count == 0 {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i >= row_count - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let mut state = Self {
+            list_state: self.list_state.clone(),
+        };
+        state.clamp_selection(job_rows(app).len());
+        render_jobs_list(app, frame, &mut state.list_state);
+    }
+}
+
+// Asks before throwing away the running job and the queue
+pub struct CancelJobsState;
+
+impl State for CancelJobsState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string(), "n".to_string()]) {
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["enter".to_string(), "y".to_string()]) {
+            app.cancel_jobs();
+            return Box::new(MainState);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_input_prompt_popup(
+            app,
+            frame,
+            format!("Cancel {} job(s)?", app.operation_manager.job_count()),
+            "y / n".to_string(),
+        );
+    }
+}
+
+// Quitting with jobs left either waits for them or cancels them first. Either way blink keeps
+// drawing until they've stopped, so a cancelled copy can clean up after itself
+#[derive(Default)]
+pub struct QuitState {
+    waiting: bool,
+}
+
+impl State for QuitState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string(), "n".to_string()]) {
+            app.running_state = RunningState::Running;
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["c".to_string()]) {
+            app.cancel_jobs();
+            app.running_state = RunningState::Finishing;
+            self.waiting = true;
+            return self;
+        }
+        if !self.waiting && kb.matches(key, &["enter".to_string(), "w".to_string()]) {
+            app.running_state = RunningState::Finishing;
+            self.waiting = true;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let jobs = app.operation_manager.job_count();
+        let lines = if self.waiting {
+            vec![
+                Line::from(format!("Waiting for {} job(s) to finish", jobs)),
+                Line::from(""),
+                Line::from("c cancel them · esc stay"),
+            ]
+        } else {
+            vec![
+                Line::from(format!("{} job(s) are still running", jobs)),
+                Line::from(""),
+                Line::from("w wait and quit · c cancel and quit · esc stay"),
+            ]
+        };
+        render_dialog_popup(app, frame, "Quit".to_string(), lines);
+    }
+}
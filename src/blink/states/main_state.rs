@@ -1,8 +1,9 @@
 use crate::blink::{
     app::{App, RunningState},
+    logging::Log,
     rendering::render_app,
     states::{
-        bookmark_states::{BookmarkListState, NewBookmarkState}, log_state::LogState, new_path_state::NewPathState, rename_path_state::RenamePathState, state_trait::State, visual_selection_states::VisualSelectionState
+        archive_states::{CreateArchiveState, ExtractArchiveState}, bookmark_states::{BookmarkListState, NewBookmarkState}, delete_state::DeleteState, history_state::HistoryState, jobs_state::{CancelJobsState, JobsState, QuitState}, log_state::LogState, paste_conflict_state::PasteConflictState, paste_link_state::PasteLinkState, new_path_state::NewPathState, pattern_rename_state::PatternRenameState, permissions_state::PermissionsState, rename_path_state::RenamePathState, search_states::{FilterState, SearchState}, sort_state::SortState, state_trait::State, trash_states::TrashListState, visual_selection_states::VisualSelectionState
     },
 };
 use ratatui::{Frame, crossterm::event::KeyCode, widgets::ListState};
@@ -14,9 +15,18 @@ impl State for MainState {
         let kb = &app.config.keybindings;
 
         if kb.matches(key, &kb.quit) {
+            if app.operation_manager.job_count() > 0 {
+                return Box::new(QuitState::default());
+            }
             app.running_state = RunningState::Done;
             return self;
         }
+        if app.archive.is_some() && kb.edits_files(key) {
+            app.log_manager.add_log(Log::Error {
+                message: "Archives are read-only, yank entries and paste them outside".to_string(),
+            });
+            return self;
+        }
         if kb.matches(key, &kb.move_down) {
             app.move_cursor_down();
             return self;
@@ -45,6 +55,16 @@ impl State for MainState {
             app.undo_last_operation();
             return self;
         }
+        if kb.matches(key, &kb.redo) {
+            app.redo_last_operation();
+            return self;
+        }
+        if kb.matches(key, &kb.cancel_jobs) {
+            if app.operation_manager.job_count() > 0 {
+                return Box::new(CancelJobsState);
+            }
+            return self;
+        }
         if kb.matches(key, &kb.yank) {
             app.yank_current_selection(false);
             return self;
@@ -54,12 +74,22 @@ impl State for MainState {
             return self;
         }
         if kb.matches(key, &kb.paste) {
-            app.paste_yanked_path();
+            return PasteConflictState::start(app, app.yank_paste_mode());
+        }
+        if kb.matches(key, &kb.paste_link) && app.yanked_archive.is_some() {
+            app.log_manager.add_log(Log::Error {
+                message: "Entries inside an archive can't be linked to".to_string(),
+            });
             return self;
         }
+        if kb.matches(key, &kb.paste_link) && app.yanked_entry_paths.is_some() {
+            return Box::new(PasteLinkState);
+        }
         if kb.matches(key, &kb.delete) {
-            app.delete_current_selection();
-            return self;
+            return DeleteState::start(app, false);
+        }
+        if kb.matches(key, &kb.permanent_delete) {
+            return DeleteState::start(app, true);
         }
         if kb.matches(key, &kb.open_default) {
             _ = app.open_in_default_app();
@@ -86,6 +116,26 @@ impl State for MainState {
                 input: String::new(),
             });
         }
+        if kb.matches(key, &kb.bulk_rename) {
+            if let Err(e) = app.bulk_rename() {
+                app.log_manager.add_log(Log::Error {
+                    message: format!("Bulk rename failed: {}", e),
+                });
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.pattern_rename) {
+            return Box::new(PatternRenameState::new(app));
+        }
+        if kb.matches(key, &kb.permissions) {
+            return Box::new(PermissionsState::new(app));
+        }
+        if kb.matches(key, &kb.archive) {
+            return Box::new(CreateArchiveState::new(app));
+        }
+        if kb.matches(key, &kb.extract) {
+            return ExtractArchiveState::start(app);
+        }
         if kb.matches(key, &kb.new_entry) {
             return Box::new(NewPathState {
                 input: String::new(),
@@ -101,6 +151,49 @@ impl State for MainState {
                 list_state: ListState::default(),
             });
         }
+        if kb.matches(key, &kb.history) {
+            return Box::new(HistoryState {
+                list_state: ListState::default(),
+            });
+        }
+        if kb.matches(key, &kb.sort) {
+            return Box::new(SortState);
+        }
+        if kb.matches(key, &kb.search) {
+            return Box::new(SearchState::new(app));
+        }
+        if kb.matches(key, &kb.search_next) {
+            app.search_next(true);
+            return self;
+        }
+        if kb.matches(key, &kb.search_prev) {
+            app.search_next(false);
+            return self;
+        }
+        if kb.matches(key, &kb.filter) {
+            return Box::new(FilterState::new(app));
+        }
+        if kb.matches(key, &kb.clear_filter) {
+            if app.filter().is_some() {
+                app.set_filter(None);
+                app.log_manager.add_log(Log::Info {
+                    message: "Filter cleared".to_string(),
+                });
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.dry_run) {
+            app.toggle_dry_run();
+            return self;
+        }
+        if kb.matches(key, &kb.jobs) {
+            return Box::new(JobsState {
+                list_state: ListState::default().with_selected(Some(0)),
+            });
+        }
+        if kb.matches(key, &kb.trash) {
+            return Box::new(TrashListState::new(app));
+        }
         if kb.matches(key, &["L".to_string()]) {
             return Box::new(LogState {
                 list_state: ListState::default(),
@@ -5,3 +5,15 @@ pub mod bookmark_states;
 pub mod new_path_state;
 pub mod rename_path_state;
 pub mod log_state;
+pub mod history_state;
+pub mod paste_conflict_state;
+pub mod trash_states;
+pub mod delete_state;
+pub mod pattern_rename_state;
+pub mod permissions_state;
+pub mod paste_link_state;
+pub mod archive_states;
+pub mod jobs_state;
+pub mod dry_run_state;
+pub mod sort_state;
+pub mod search_states;
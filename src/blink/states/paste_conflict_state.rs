@@ -0,0 +1,180 @@
+use crate::blink::{
+    app::App,
+    operations::{ConflictResolution, PasteItem, PasteMode, progress::Workload},
+    rendering::{format_size, format_system_time, render_app, render_dialog_popup},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, text::Line};
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+// Asks what to do with each yanked path whose target already exists in the cwd,
+// the paste itself only runs once every conflict has an answer
+pub struct PasteConflictState {
+    items: Vec<PasteItem>,
+    mode: PasteMode,
+    current: usize,
+    apply_to_all: bool,
+    comparison: Option<(EntrySummary, EntrySummary)>,
+}
+
+// The size is measured in the background since either side can be a big directory
+struct EntrySummary {
+    workload: Arc<Mutex<Option<Workload>>>,
+    modified: Option<SystemTime>,
+}
+
+impl EntrySummary {
+    fn new(app: &mut App, path: &Path) -> Self {
+        Self {
+            workload: app.measure_paths(vec![path.to_path_buf()]),
+            modified: fs::metadata(path).and_then(|m| m.modified()).ok(),
+        }
+    }
+
+    fn describe(&self, label: &str) -> String {
+        let size = match *self.workload.lock().unwrap() {
+            Some(workload) => format!(
+                "{}, {} file(s)",
+                format_size(workload.bytes),
+                workload.files
+            ),
+            None => "\u{2026}".to_string(),
+        };
+        format!(
+            "{}: {}, modified {}",
+            label,
+            size,
+            self.modified
+                .map(format_system_time)
+                .unwrap_or_else(|| "unknown".to_string())
+        )
+    }
+}
+
+impl PasteConflictState {
+    pub fn new(items: Vec<PasteItem>, mode: PasteMode, current: usize) -> Self {
+        Self {
+            items,
+            mode,
+            current,
+            apply_to_all: false,
+            comparison: None,
+        }
+    }
+
+    // Pastes the yanked paths straight away unless one of them needs an answer first
+    pub fn start(app: &mut App, mode: PasteMode) -> Box<dyn State> {
+        if app.yanked_archive.is_some() {
+            app.paste_archive_members();
+            return Box::new(MainState);
+        }
+        let items = app.plan_paste(mode);
+        if let Some(current) = items.iter().position(|item| item.needs_resolution()) {
+            return Box::new(Self::new(items, mode, current));
+        }
+        app.paste_yanked_path(items, mode);
+        Box::new(MainState)
+    }
+
+    fn resolve(
+        mut self: Box<Self>,
+        resolution: ConflictResolution,
+        app: &mut App,
+    ) -> Box<dyn State> {
+        if self.apply_to_all {
+            for item in self.items[self.current..].iter_mut() {
+                if item.needs_resolution() {
+                    item.resolution = Some(resolution);
+                }
+            }
+        } else {
+            self.items[self.current].resolution = Some(resolution);
+        }
+
+        match self.items.iter().position(|item| item.needs_resolution()) {
+            Some(next) => {
+                self.current = next;
+                self.comparison = None;
+                self
+            }
+            None => {
+                app.paste_yanked_path(self.items, self.mode);
+                Box::new(MainState)
+            }
+        }
+    }
+}
+
+impl State for PasteConflictState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string()]) {
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["o".to_string()]) {
+            return self.resolve(ConflictResolution::Overwrite, app);
+        }
+        if kb.matches(key, &["s".to_string()]) {
+            return self.resolve(ConflictResolution::Skip, app);
+        }
+        if kb.matches(key, &["k".to_string()]) {
+            return self.resolve(ConflictResolution::KeepBoth, app);
+        }
+        if kb.matches(key, &["a".to_string()]) {
+            self.apply_to_all = !self.apply_to_all;
+            return self;
+        }
+        if kb.matches(key, &["c".to_string()]) {
+            self.comparison = match self.comparison {
+                Some(_) => None,
+                None => {
+                    let item = &self.items[self.current];
+                    Some((
+                        EntrySummary::new(app, &item.source),
+                        EntrySummary::new(app, &item.target),
+                    ))
+                }
+            };
+            return self;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let item = &self.items[self.current];
+        let remaining = self
+            .items
+            .iter()
+            .filter(|item| item.needs_resolution())
+            .count();
+
+        let mut lines = vec![
+            Line::from(format!("{} already exists", item.target.display())),
+            Line::from(format!("{} conflict(s) left", remaining)),
+            Line::from(""),
+        ];
+        if let Some((source, target)) = &self.comparison {
+            lines.push(Line::from(source.describe("Source")));
+            lines.push(Line::from(target.describe("Target")));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(
+            "o: overwrite (old one goes to the trash) | s: skip | k: keep both",
+        ));
+        lines.push(Line::from(format!(
+            "c: compare | a: apply to all [{}] | esc: cancel paste",
+            if self.apply_to_all { "on" } else { "off" }
+        )));
+
+        render_dialog_popup(app, frame, "Paste conflict".to_string(), lines);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::blink::{
+    app::App,
+    operations::{PasteMode, link_operation::LinkKind},
+    rendering::{render_app, render_dialog_popup},
+    states::{main_state::MainState, paste_conflict_state::PasteConflictState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, text::Line};
+
+// Asks which kind of link to make for every yanked path before handing off to the paste
+pub struct PasteLinkState;
+
+impl State for PasteLinkState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        let kind = if kb.matches(key, &["a".to_string()]) {
+            LinkKind::AbsoluteSymlink
+        } else if kb.matches(key, &["r".to_string()]) {
+            LinkKind::RelativeSymlink
+        } else if kb.matches(key, &["h".to_string()]) {
+            LinkKind::HardLink
+        } else if kb.matches(key, &["esc".to_string()]) {
+            return Box::new(MainState);
+        } else {
+            return self;
+        };
+
+        PasteConflictState::start(app, PasteMode::Link(kind))
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let count = app.yanked_entry_paths.as_ref().map_or(0, Vec::len);
+        let lines = vec![
+            Line::from(format!(
+                "Link {} yanked item(s) into this directory as",
+                count
+            )),
+            Line::from(""),
+            Line::from("a: absolute symlink"),
+            Line::from("r: relative symlink"),
+            Line::from("h: hard link"),
+            Line::from(""),
+            Line::from("esc: cancel"),
+        ];
+
+        render_dialog_popup(app, frame, "Paste as link".to_string(), lines);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::blink::{
+    app::App,
+    operations::rename_pattern::{self, PreviewRow},
+    rendering::{render_app, render_rename_preview},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode};
+use std::path::PathBuf;
+
+// Renames the visual selection, or the entry under the cursor, by a regex or template while
+// showing what every name turns into. Nothing runs while any row has a problem
+pub struct PatternRenameState {
+    input: String,
+    paths: Vec<PathBuf>,
+    // Worked out again whenever the input changes rather than on every frame
+    preview: Result<Vec<PreviewRow>, String>,
+}
+
+impl PatternRenameState {
+    pub fn new(app: &App) -> Self {
+        let paths = app.selected_paths();
+
+        Self {
+            input: String::new(),
+            paths,
+            preview: Ok(Vec::new()),
+        }
+    }
+
+    fn update_preview(&mut self) {
+        self.preview = if self.input.is_empty() {
+            Ok(Vec::new())
+        } else {
+            rename_pattern::preview(&self.input, &self.paths)
+        };
+    }
+
+    fn close(app: &mut App) -> Box<dyn State> {
+        if app.visual_mode {
+            app.toggle_visual_mode();
+        }
+        Box::new(MainState)
+    }
+}
+
+impl State for PatternRenameState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if let KeyCode::Char(c) = key {
+            self.input.push(c);
+            self.update_preview();
+            return self;
+        }
+        if kb.matches(key, &["backspace".to_string()]) {
+            self.input.pop();
+            self.update_preview();
+            return self;
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            // The directory may have changed since the last key, so check once more
+            self.update_preview();
+            let Ok(rows) = &self.preview else {
+                return self;
+            };
+            if self.input.is_empty() || rows.iter().any(|row| row.problem.is_some()) {
+                return self;
+            }
+
+            let renames = rows
+                .iter()
+                .map(|row| (row.source.clone(), row.target.clone()))
+                .collect();
+            app.rename_paths(renames);
+            return Self::close(app);
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            return Self::close(app);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_rename_preview(app, frame, &self.input, &self.preview);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::blink::{
+    app::App,
+    logging::Log,
+    operations::permissions_operation::PermissionChange,
+    rendering::{render_app, render_dialog_popup},
+    states::{main_state::MainState, state_trait::State},
+    users,
+};
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Grid,
+    Octal,
+    Owner,
+    Group,
+    Recursive,
+}
+
+impl Field {
+    fn next(self) -> Self {
+        match self {
+            Field::Grid => Field::Octal,
+            Field::Octal => Field::Owner,
+            Field::Owner => Field::Group,
+            Field::Group => Field::Recursive,
+            Field::Recursive => Field::Grid,
+        }
+    }
+}
+
+// Edits the mode and ownership of the current entry or the visual selection. The dialog starts
+// out with the first path's values and only what was actually edited gets applied. Bits toggled
+// in the grid are added to or removed from each path's own mode, a full octal mode replaces it
+pub struct PermissionsState {
+    paths: Vec<PathBuf>,
+    mode: u32,
+    original_mode: u32,
+    mode_edited: bool,
+    absolute: bool,
+    // Typed digits that haven't made up a whole mode yet
+    octal_pending: bool,
+    octal: String,
+    owner: String,
+    original_owner: String,
+    group: String,
+    original_group: String,
+    recursive: bool,
+    focus: Field,
+    // Position in the 3x3 grid, rows are user, group and other, columns are r, w and x
+    cell: usize,
+}
+
+impl PermissionsState {
+    pub fn new(app: &App) -> Self {
+        let paths = app.selected_paths();
+        let (mode, owner, group) = paths
+            .first()
+            .and_then(|path| current_permissions(path))
+            .unwrap_or_default();
+
+        Self {
+            paths,
+            mode,
+            original_mode: mode,
+            mode_edited: false,
+            absolute: false,
+            octal_pending: false,
+            octal: format!("{:04o}", mode),
+            owner: owner.clone(),
+            original_owner: owner,
+            group: group.clone(),
+            original_group: group,
+            recursive: false,
+            focus: Field::Grid,
+            cell: 0,
+        }
+    }
+
+    fn close(app: &mut App) -> Box<dyn State> {
+        if app.visual_mode {
+            app.toggle_visual_mode();
+        }
+        Box::new(MainState)
+    }
+
+    fn bit(cell: usize) -> u32 {
+        1 << (8 - cell)
+    }
+
+    fn set_mode(&mut self, mode: u32) {
+        self.mode = mode;
+        self.mode_edited = true;
+    }
+
+    fn apply(mut self: Box<Self>, app: &mut App) -> Box<dyn State> {
+        // Three digits are a whole mode too, only while typing could they be the start of four
+        if self.octal_pending {
+            match self.octal.len() {
+                3 => {
+                    let mode = u32::from_str_radix(&self.octal, 8).unwrap_or_default();
+                    self.set_mode(mode);
+                    self.absolute = true;
+                }
+                _ => {
+                    app.log_manager.add_log(Log::Error {
+                        message: format!("Incomplete mode {}", self.octal),
+                    });
+                    self.focus = Field::Octal;
+                    return self;
+                }
+            }
+        }
+        let owner = match self.owner.trim() {
+            name if name == self.original_owner => None,
+            name => match users::parse_user(name) {
+                Some(uid) => Some(uid),
+                None => {
+                    app.log_manager.add_log(Log::Error {
+                        message: format!("Unknown user {}", name),
+                    });
+                    self.focus = Field::Owner;
+                    return self;
+                }
+            },
+        };
+        let group = match self.group.trim() {
+            name if name == self.original_group => None,
+            name => match users::parse_group(name) {
+                Some(gid) => Some(gid),
+                None => {
+                    app.log_manager.add_log(Log::Error {
+                        message: format!("Unknown group {}", name),
+                    });
+                    self.focus = Field::Group;
+                    return self;
+                }
+            },
+        };
+
+        let mut change = PermissionChange {
+            owner,
+            group,
+            ..PermissionChange::default()
+        };
+        if self.mode_edited && self.absolute {
+            change.mode = Some(self.mode);
+        } else if self.mode_edited {
+            change.set = self.mode & !self.original_mode;
+            change.clear = self.original_mode & !self.mode;
+        }
+        let edited = change.changes_mode() || change.owner.is_some() || change.group.is_some();
+        if edited && !self.paths.is_empty() {
+            app.change_permissions(self.paths, change, self.recursive);
+        }
+        Self::close(app)
+    }
+}
+
+impl State for PermissionsState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string()]) {
+            return Self::close(app);
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            return self.apply(app);
+        }
+        if kb.matches(key, &["tab".to_string()]) {
+            self.focus = self.focus.next();
+            return self;
+        }
+
+        match self.focus {
+            Field::Grid => {
+                if kb.matches(key, &kb.move_up) && self.cell >= 3 {
+                    self.cell -= 3;
+                } else if kb.matches(key, &kb.move_down) && self.cell < 6 {
+                    self.cell += 3;
+                } else if kb.matches(key, &kb.go_back) && !self.cell.is_multiple_of(3) {
+                    self.cell -= 1;
+                } else if kb.matches(key, &kb.go_forward) && self.cell % 3 < 2 {
+                    self.cell += 1;
+                } else if kb.matches(key, &[" ".to_string()]) {
+                    self.set_mode(self.mode ^ Self::bit(self.cell));
+                    self.octal = format!("{:04o}", self.mode);
+                    self.octal_pending = false;
+                }
+            }
+            Field::Octal => {
+                if let KeyCode::Char(c @ '0'..='7') = key
+                    && self.octal.len() < 4
+                {
+                    self.octal.push(c);
+                    self.octal_pending = true;
+                } else if kb.matches(key, &["backspace".to_string()]) {
+                    self.octal.pop();
+                    self.octal_pending = true;
+                }
+                if self.octal_pending
+                    && self.octal.len() == 4
+                    && let Ok(mode) = u32::from_str_radix(&self.octal, 8)
+                {
+                    self.set_mode(mode);
+                    self.absolute = true;
+                    self.octal_pending = false;
+                }
+            }
+            Field::Owner | Field::Group => {
+                let input = if self.focus == Field::Owner {
+                    &mut self.owner
+                } else {
+                    &mut self.group
+                };
+                if let KeyCode::Char(c) = key {
+                    input.push(c);
+                } else if kb.matches(key, &["backspace".to_string()]) {
+                    input.pop();
+                }
+            }
+            Field::Recursive => {
+                if kb.matches(key, &[" ".to_string()]) {
+                    self.recursive = !self.recursive;
+                }
+            }
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let focused = Style::default()
+            .bg(app.config.colors.selected_bg.to_ratatui_color())
+            .add_modifier(Modifier::BOLD);
+        let style_for = |field: Field| {
+            if self.focus == field {
+                focused
+            } else {
+                Style::default()
+            }
+        };
+
+        let mut lines = vec![
+            Line::from(match self.paths.as_slice() {
+                [path] => path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                _ => format!(
+                    "{} items (showing the first, toggled bits apply to each)",
+                    self.paths.len()
+                ),
+            }),
+            Line::from(""),
+            Line::from("         read  write  exec"),
+        ];
+
+        for (row, label) in ["user", "group", "other"].iter().enumerate() {
+            let mut spans = vec![Span::raw(format!("{:<8}", label))];
+            for col in 0..3 {
+                let cell = row * 3 + col;
+                let mark = if self.mode & Self::bit(cell) != 0 {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let style = if self.focus == Field::Grid && self.cell == cell {
+                    focused
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(mark, style));
+                spans.push(Span::raw("  "));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::raw("Octal:  "),
+            Span::styled(format!("{:<4}", self.octal), style_for(Field::Octal)),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw("Owner:  "),
+            Span::styled(self.owner.clone(), style_for(Field::Owner)),
+        ]));
+        lines.push(Line::from(vec![
+            Span::raw("Group:  "),
+            Span::styled(self.group.clone(), style_for(Field::Group)),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} Apply recursively",
+                if self.recursive { "[x]" } else { "[ ]" }
+            ),
+            style_for(Field::Recursive),
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "tab next field · space toggle · enter apply · esc cancel",
+        ));
+
+        render_dialog_popup(app, frame, "Permissions".to_string(), lines);
+    }
+}
+
+#[cfg(unix)]
+fn current_permissions(path: &std::path::Path) -> Option<(u32, String, String)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    let owner = users::user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string());
+    let group = users::group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string());
+    Some((metadata.mode() & 0o7777, owner, group))
+}
+
+#[cfg(not(unix))]
+fn current_permissions(_path: &std::path::Path) -> Option<(u32, String, String)> {
+    None
+}
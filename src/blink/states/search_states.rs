@@ -0,0 +1,170 @@
+use crate::blink::{
+    app::App,
+    filter::{MatchMode, NameMatcher},
+    logging::Log,
+    rendering::{render_app, render_input_popup},
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode};
+
+// Jumps to the first match as the query is typed. Enter keeps the query for n and N, esc goes
+// back to where the cursor was and to the previous search
+pub struct SearchState {
+    input: String,
+    origin: Option<usize>,
+    previous: Option<NameMatcher>,
+}
+
+impl SearchState {
+    pub fn new(app: &App) -> Self {
+        Self {
+            input: String::new(),
+            origin: app.list_state.selected(),
+            previous: app.search.clone(),
+        }
+    }
+
+    fn update(&self, app: &mut App) {
+        app.search = if self.input.is_empty() {
+            None
+        } else {
+            NameMatcher::new(&self.input, MatchMode::Substring).ok()
+        };
+        let found = app.find_match(self.origin.unwrap_or(0), true);
+        app.list_state.select(found.or(self.origin));
+    }
+}
+
+impl State for SearchState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if let KeyCode::Char(c) = key {
+            self.input.push(c);
+            self.update(app);
+            return self;
+        }
+        if kb.matches(key, &["backspace".to_string()]) {
+            self.input.pop();
+            self.update(app);
+            return self;
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            if self.input.is_empty() {
+                app.search = self.previous;
+            } else if app.find_match(0, true).is_none() {
+                app.log_manager.add_log(Log::Warning {
+                    message: format!("Pattern not found: {}", self.input),
+                });
+            }
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            app.search = self.previous;
+            app.list_state.select(self.origin);
+            return Box::new(MainState);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_input_popup(app, frame, "Search".to_string(), format!("/{}", self.input))
+    }
+}
+
+// Narrows the cwd to the matching entries while typing, tab switches between substring, glob
+// and regex. The filter stays on the directory after enter, esc puts back the one it had before
+pub struct FilterState {
+    input: String,
+    mode: MatchMode,
+    previous: Option<NameMatcher>,
+    error: Option<String>,
+}
+
+impl FilterState {
+    pub fn new(app: &App) -> Self {
+        let previous = app.filter().cloned();
+        Self {
+            input: previous
+                .as_ref()
+                .map(|filter| filter.pattern.clone())
+                .unwrap_or_default(),
+            mode: previous
+                .as_ref()
+                .map(|filter| filter.mode)
+                .unwrap_or_default(),
+            previous,
+            error: None,
+        }
+    }
+
+    // Half typed regexes are often invalid, the listing keeps the last filter that worked
+    fn apply(&mut self, app: &mut App) {
+        if self.input.is_empty() {
+            self.error = None;
+            app.set_filter(None);
+            return;
+        }
+        match NameMatcher::new(&self.input, self.mode) {
+            Ok(filter) => {
+                self.error = None;
+                app.set_filter(Some(filter));
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+impl State for FilterState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if let KeyCode::Char(c) = key {
+            self.input.push(c);
+            self.apply(app);
+            return self;
+        }
+        if kb.matches(key, &["backspace".to_string()]) {
+            self.input.pop();
+            self.apply(app);
+            return self;
+        }
+        if kb.matches(key, &["tab".to_string()]) {
+            self.mode = self.mode.next();
+            self.apply(app);
+            return self;
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            if let Some(error) = self.error.take() {
+                app.log_manager.add_log(Log::Error {
+                    message: format!("Invalid filter {}: {}", self.input, error),
+                });
+                app.set_filter(self.previous);
+            }
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            app.set_filter(self.previous);
+            return Box::new(MainState);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let hint = match &self.error {
+            Some(error) => error.clone(),
+            None => format!("tab: {}", self.mode.next().describe()),
+        };
+        render_input_popup(
+            app,
+            frame,
+            format!("Filter ({})", self.mode.describe()),
+            format!("Pattern: {}\n{}", self.input, hint),
+        )
+    }
+}
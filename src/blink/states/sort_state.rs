@@ -0,0 +1,80 @@
+use crate::blink::{
+    app::App,
+    rendering::{render_app, render_dialog_popup},
+    sorting::SortKey,
+    states::{main_state::MainState, state_trait::State},
+};
+use ratatui::{Frame, crossterm::event::KeyCode, text::Line};
+
+const KEYS: [(&str, SortKey); 5] = [
+    ("n", SortKey::Name),
+    ("s", SortKey::Size),
+    ("m", SortKey::Modified),
+    ("c", SortKey::Created),
+    ("e", SortKey::Extension),
+];
+
+// Picks how the cwd is sorted. Every change applies right away and the menu stays open, picking
+// the key that's already active flips the direction
+pub struct SortState;
+
+impl State for SortState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+        let mut order = app.sort_order();
+
+        if kb.matches(key, &["esc".to_string()]) || kb.matches(key, &["enter".to_string()]) {
+            return Box::new(MainState);
+        }
+
+        if let Some((_, sort_key)) = KEYS
+            .iter()
+            .find(|(binding, _)| kb.matches(key, &[binding.to_string()]))
+        {
+            if order.key == *sort_key {
+                order.reverse = !order.reverse;
+            } else {
+                order.key = *sort_key;
+                order.reverse = false;
+            }
+        } else if kb.matches(key, &["r".to_string()]) {
+            order.reverse = !order.reverse;
+        } else if kb.matches(key, &["d".to_string()]) {
+            order.dirs_first = !order.dirs_first;
+        } else {
+            return self;
+        }
+
+        app.set_sort_order(order);
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+
+        let order = app.sort_order();
+        let mark = |active: bool| if active { "\u{25cf}" } else { " " };
+        let mut lines = vec![
+            Line::from(format!("Sorted by {}", order.describe())),
+            Line::from(""),
+        ];
+        for (binding, sort_key) in KEYS {
+            lines.push(Line::from(format!(
+                "{} {}: {}",
+                mark(order.key == sort_key),
+                binding,
+                sort_key.describe()
+            )));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("{} r: reverse", mark(order.reverse))));
+        lines.push(Line::from(format!(
+            "{} d: directories first",
+            mark(order.dirs_first)
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from("esc: close"));
+
+        render_dialog_popup(app, frame, "Sort".to_string(), lines);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::blink::{
+    app::App,
+    rendering::{
+        format_size, render_app, render_input_popup, render_input_prompt_popup, render_trash_list,
+    },
+    states::{main_state::MainState, state_trait::State},
+    trash_manager::TrashItem,
+};
+use ratatui::{Frame, crossterm::event::KeyCode, widgets::ListState};
+use std::sync::Arc;
+
+pub struct TrashListState {
+    pub list_state: ListState,
+    pub items: Vec<TrashItem>,
+}
+pub struct RestoreTrashItemState {
+    browser: TrashListState,
+    input: String,
+}
+pub struct PurgeTrashItemState {
+    browser: TrashListState,
+}
+pub struct EmptyTrashState {
+    browser: TrashListState,
+}
+
+impl TrashListState {
+    pub fn new(app: &mut App) -> Self {
+        let items = app.operation_manager.trash_items();
+        app.measure_trash_items(items.clone());
+        let mut list_state = ListState::default();
+        if !items.is_empty() {
+            list_state.select(Some(0));
+        }
+        Self { list_state, items }
+    }
+
+    // Reads the trash again after something changed, keeping the cursor roughly in place.
+    // Items that were already listed keep their size, only new ones get measured
+    fn reload(mut self, app: &mut App) -> Self {
+        let mut items = app.operation_manager.trash_items();
+        let mut unmeasured = Vec::new();
+        for item in items.iter_mut() {
+            match self
+                .items
+                .iter()
+                .find(|old| old.trash_path == item.trash_path)
+            {
+                Some(old) => item.size = Arc::clone(&old.size),
+                None => unmeasured.push(item.clone()),
+            }
+        }
+        app.measure_trash_items(unmeasured);
+        self.items = items;
+        let selected = match self.list_state.selected() {
+            _ if self.items.is_empty() => None,
+            Some(i) => Some(i.min(self.items.len() - 1)),
+            None => Some(0),
+        };
+        self.list_state.select(selected);
+        self
+    }
+
+    fn selected_item(&self) -> Option<&TrashItem> {
+        self.list_state.selected().and_then(|i| self.items.get(i))
+    }
+}
+
+impl State for TrashListState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &kb.quit) {
+            return Box::new(MainState);
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            if let Some(item) = self.selected_item().cloned() {
+                app.restore_trash_item(&item, None);
+                return Box::new(self.reload(app));
+            }
+            return self;
+        }
+        if kb.matches(key, &["m".to_string()]) {
+            if self.selected_item().is_some() {
+                return Box::new(RestoreTrashItemState {
+                    browser: *self,
+                    input: String::new(),
+                });
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.delete) {
+            if self.selected_item().is_some() {
+                return Box::new(PurgeTrashItemState { browser: *self });
+            }
+            return self;
+        }
+        if kb.matches(key, &["E".to_string()]) {
+            if !self.items.is_empty() {
+                return Box::new(EmptyTrashState { browser: *self });
+            }
+            return self;
+        }
+        if kb.matches(key, &kb.move_up) {
+            if self.items.is_empty() {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i == 0 {
+                        self.items.len() - 1
+                    } else {
+                        i - 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+        if kb.matches(key, &kb.move_down) {
+            if self.items.is_empty() {
+                return self;
+            }
+
+            let i = match self.list_state.selected() {
+                Some(i) => {
+                    if i >= self.items.len() - 1 {
+                        0
+                    } else {
+                        i + 1
+                    }
+                }
+                _ => 0,
+            };
+
+            self.list_state.select(Some(i));
+            return self;
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        render_app(app, frame);
+        render_trash_list(app, frame, &self.items, &mut self.list_state.clone());
+    }
+}
+
+impl State for RestoreTrashItemState {
+    fn handle_input(mut self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if let KeyCode::Char(c) = key {
+            self.input.push(c);
+            return self;
+        }
+        if kb.matches(key, &["backspace".to_string()]) {
+            self.input.pop();
+            return self;
+        }
+        if kb.matches(key, &["enter".to_string()]) {
+            if let Some(item) = self.browser.selected_item().cloned() {
+                app.restore_trash_item(&item, Some(&self.input));
+            }
+            return Box::new(self.browser.reload(app));
+        }
+        if kb.matches(key, &["esc".to_string()]) {
+            return Box::new(self.browser);
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        self.browser.render(app, frame);
+        render_input_popup(
+            app,
+            frame,
+            "Restore to".to_string(),
+            format!("Path: {}", self.input),
+        )
+    }
+}
+
+impl State for PurgeTrashItemState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string(), "n".to_string()]) {
+            return Box::new(self.browser);
+        }
+        if kb.matches(key, &["enter".to_string(), "y".to_string()]) {
+            if let Some(item) = self.browser.selected_item().cloned() {
+                app.purge_trash_item(&item);
+            }
+            return Box::new(self.browser.reload(app));
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        self.browser.render(app, frame);
+        render_input_prompt_popup(
+            app,
+            frame,
+            "Delete permanently?".to_string(),
+            "y / n".to_string(),
+        );
+    }
+}
+
+impl State for EmptyTrashState {
+    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
+        let kb = &app.config.keybindings;
+
+        if kb.matches(key, &["esc".to_string(), "n".to_string()]) {
+            return Box::new(self.browser);
+        }
+        if kb.matches(key, &["enter".to_string(), "y".to_string()]) {
+            app.empty_trash();
+            return Box::new(self.browser.reload(app));
+        }
+
+        self
+    }
+
+    fn render(&self, app: &App, frame: &mut Frame) {
+        self.browser.render(app, frame);
+        let sizes: Vec<Option<u64>> = self.browser.items.iter().map(TrashItem::size).collect();
+        render_input_prompt_popup(
+            app,
+            frame,
+            "Empty trash?".to_string(),
+            format!(
+                "{} item(s), {}{} will be gone for good. y / n",
+                self.browser.items.len(),
+                format_size(sizes.iter().flatten().sum()),
+                if sizes.contains(&None) {
+                    "\u{2026}"
+                } else {
+                    ""
+                }
+            ),
+        );
+    }
+}
@@ -1,12 +1,16 @@
 use crate::blink::{
     app::App,
-    rendering::{render_app, render_input_prompt_popup},
-    states::{main_state::MainState, state_trait::State},
+    logging::Log,
+    rendering::render_app,
+    states::{
+        archive_states::CreateArchiveState, delete_state::DeleteState, main_state::MainState,
+        pattern_rename_state::PatternRenameState, permissions_state::PermissionsState,
+        state_trait::State,
+    },
 };
 use ratatui::{Frame, crossterm::event::KeyCode};
 
 pub struct VisualSelectionState;
-pub struct DeleteVisualSelectionState;
 
 impl State for VisualSelectionState {
     fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
@@ -16,6 +20,12 @@ impl State for VisualSelectionState {
             app.toggle_visual_mode();
             return Box::new(MainState);
         }
+        if app.archive.is_some() && kb.edits_files(key) {
+            app.log_manager.add_log(Log::Error {
+                message: "Archives are read-only, yank entries and paste them outside".to_string(),
+            });
+            return self;
+        }
         if kb.matches(key, &kb.move_down) {
             app.move_cursor_down();
             return self;
@@ -34,30 +44,29 @@ impl State for VisualSelectionState {
             app.toggle_visual_mode();
             return Box::new(MainState);
         }
-        if kb.matches(key, &kb.delete) {
-            return Box::new(DeleteVisualSelectionState);
-        }
-
-        self
-    }
-
-    fn render(&self, app: &App, frame: &mut Frame) {
-        render_app(app, frame);
-    }
-}
-
-impl State for DeleteVisualSelectionState {
-    fn handle_input(self: Box<Self>, key: KeyCode, app: &mut App) -> Box<dyn State> {
-        let kb = &app.config.keybindings;
-
-        if kb.matches(key, &vec!["esc".to_string(), "n".to_string()]) {
+        if kb.matches(key, &kb.bulk_rename) {
+            if let Err(e) = app.bulk_rename() {
+                app.log_manager.add_log(Log::Error {
+                    message: format!("Bulk rename failed: {}", e),
+                });
+            }
             app.toggle_visual_mode();
             return Box::new(MainState);
         }
-        if kb.matches(key, &vec!["enter".to_string(), "y".to_string()]) {
-            app.delete_current_selection();
-            app.toggle_visual_mode();
-            return Box::new(MainState);
+        if kb.matches(key, &kb.pattern_rename) {
+            return Box::new(PatternRenameState::new(app));
+        }
+        if kb.matches(key, &kb.permissions) {
+            return Box::new(PermissionsState::new(app));
+        }
+        if kb.matches(key, &kb.archive) {
+            return Box::new(CreateArchiveState::new(app));
+        }
+        if kb.matches(key, &kb.delete) {
+            return DeleteState::start(app, false);
+        }
+        if kb.matches(key, &kb.permanent_delete) {
+            return DeleteState::start(app, true);
         }
 
         self
@@ -65,11 +74,5 @@ impl State for DeleteVisualSelectionState {
 
     fn render(&self, app: &App, frame: &mut Frame) {
         render_app(app, frame);
-        render_input_prompt_popup(
-            app,
-            frame,
-            "Delete selection?".to_string(),
-            "y / n".to_string(),
-        );
     }
 }
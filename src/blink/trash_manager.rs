@@ -1,19 +1,42 @@
+use crate::blink::operations::{progress::Progress, transfer};
+use chrono::{Local, NaiveDateTime};
+use std::ffi::OsString;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex};
 
+const INFO_EXTENSION: &str = "trashinfo";
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+// Lays the trash out the way the FreeDesktop trash spec does, a files directory holding the
+// trashed entries and an info directory with a .trashinfo file for each of them. That way
+// file managers and trash-cli can see and restore what blink trashed and the other way around
 #[derive(Debug, Clone)]
 pub struct TrashManager {
     trash_dir: PathBuf,
 }
 
+// An entry in one of the trash directories, read back from its .trashinfo file. Measuring a
+// big directory takes a while, so the size is filled in later like a preview
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    pub trash_path: PathBuf,
+    pub original_path: PathBuf,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub size: Arc<Mutex<Option<u64>>>,
+}
+
+impl TrashItem {
+    pub fn size(&self) -> Option<u64> {
+        self.size.lock().ok().and_then(|size| *size)
+    }
+}
+
 impl TrashManager {
     pub fn new() -> io::Result<Self> {
         let trash_dir = Self::get_trash_directory()?;
-        if !trash_dir.exists() {
-            fs::create_dir_all(&trash_dir)?;
-        }
+        create_trash_dirs(&trash_dir)?;
         Ok(Self { trash_dir })
     }
 
@@ -37,9 +60,9 @@ impl TrashManager {
     fn get_trash_directory() -> io::Result<PathBuf> {
         // Linux: Use XDG trash or fallback to ~/.local/share/Trash
         if let Ok(xdg_data) = std::env::var("XDG_DATA_HOME") {
-            Ok(PathBuf::from(xdg_data).join("Trash").join("files"))
+            Ok(PathBuf::from(xdg_data).join("Trash"))
         } else if let Ok(home) = std::env::var("HOME") {
-            Ok(PathBuf::from(home).join(".local/share/Trash/files"))
+            Ok(PathBuf::from(home).join(".local/share/Trash"))
         } else {
             Ok(std::env::temp_dir().join("app_trash"))
         }
@@ -51,37 +74,453 @@ impl TrashManager {
         Ok(std::env::temp_dir().join("app_trash"))
     }
 
-    fn generate_trash_path(&self, original_path: &Path) -> PathBuf {
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    // The trash a path would go to, along with the top directory of its mount when that isn't
+    // the home trash. Paths in those trashes are recorded relative to the top directory. Only
+    // looks, so planning a delete doesn't leave trash directories behind on other mounts
+    pub fn trash_dir_for(&self, path: &Path) -> (PathBuf, Option<PathBuf>) {
+        self.trash_dir_candidates(path).remove(0)
+    }
 
-        let filename = original_path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy();
+    // The trashes a path could go to, best first. The home trash always comes last
+    fn trash_dir_candidates(&self, path: &Path) -> Vec<(PathBuf, Option<PathBuf>)> {
+        let mut candidates = Vec::new();
+
+        #[cfg(target_os = "linux")]
+        if !transfer::same_device(path, &self.trash_dir)
+            && let Some(topdir) = mount_topdir(path)
+        {
+            candidates.extend(
+                topdir_trashes(&topdir)
+                    .into_iter()
+                    .map(|trash_dir| (trash_dir, Some(topdir.clone()))),
+            );
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        let _ = path;
+
+        candidates.push((self.trash_dir.clone(), None));
+        candidates
+    }
 
-        self.trash_dir.join(format!("{}_{}", timestamp, filename))
+    // Creates the first trash for path that can be created, right before something goes in it
+    fn create_trash_dir_for(&self, path: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+        let mut candidates = self.trash_dir_candidates(path);
+        let home = candidates.pop().unwrap_or((self.trash_dir.clone(), None));
+        for (trash_dir, topdir) in candidates {
+            if create_trash_dirs(&trash_dir).is_ok() {
+                return Ok((trash_dir, topdir));
+            }
+        }
+        create_trash_dirs(&home.0)?;
+        Ok(home)
     }
 
-    pub fn move_to_trash(&self, path: &Path) -> io::Result<PathBuf> {
-        let trash_path = self.generate_trash_path(path);
-        fs::rename(path, &trash_path)?;
+    pub fn move_to_trash(&self, path: &Path, progress: &Progress) -> io::Result<PathBuf> {
+        let path = std::path::absolute(path)?;
+        let (trash_dir, topdir) = self.create_trash_dir_for(&path)?;
+
+        let (name, info_path, mut info_file) = reserve_name(&trash_dir, &path)?;
+        let recorded = topdir
+            .as_deref()
+            .and_then(|top| path.strip_prefix(top).ok())
+            .unwrap_or(&path);
+        let info = format_info(recorded, Local::now().naive_local());
+
+        let trash_path = trash_dir.join("files").join(name);
+        let status = info_file
+            .write_all(info.as_bytes())
+            .and_then(|_| transfer::move_path(&path, &trash_path, false, progress));
+        if let Err(e) = status {
+            _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
         Ok(trash_path)
     }
 
     pub fn restore_from_trash(&self, trash_path: &Path, original_path: &Path) -> io::Result<()> {
-        fs::rename(trash_path, original_path)
+        // A rename would silently replace a file that took the original's place
+        if fs::symlink_metadata(original_path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", original_path.display()),
+            ));
+        }
+        if let Some(parent) = original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        transfer::move_path(trash_path, original_path, false, &Progress::default())?;
+        if let Some(info_path) = info_path_for(trash_path) {
+            _ = fs::remove_file(info_path);
+        }
+        Ok(())
+    }
+
+    // Everything in the home trash and the trashes on other mounts, including what other
+    // programs put there. Entries without a readable .trashinfo file are left out
+    pub fn list(&self) -> Vec<TrashItem> {
+        self.trash_dirs()
+            .iter()
+            .flat_map(|(trash_dir, topdir)| read_trash_dir(trash_dir, topdir.as_deref()))
+            .collect()
+    }
+
+    // Removes an entry and its .trashinfo file for good
+    pub fn purge(&self, trash_path: &Path) -> io::Result<()> {
+        transfer::remove_path(trash_path)?;
+        if let Some(info_path) = info_path_for(trash_path) {
+            _ = fs::remove_file(info_path);
+        }
+        Ok(())
     }
 
-    /*
+    // Clears out every trash directory, including entries other programs left without info
     pub fn empty_trash(&self) -> io::Result<()> {
-        if self.trash_dir.exists() {
-            fs::remove_dir_all(&self.trash_dir)?;
-            fs::create_dir_all(&self.trash_dir)?;
+        for (trash_dir, _) in self.trash_dirs() {
+            for sub_dir in ["files", "info"] {
+                let Ok(read_dir) = fs::read_dir(trash_dir.join(sub_dir)) else {
+                    continue;
+                };
+                for entry in read_dir {
+                    transfer::remove_path(&entry?.path())?;
+                }
+            }
         }
         Ok(())
     }
-    */
+
+    // The home trash followed by the trashes on other mounts, paired with their top directory
+    fn trash_dirs(&self) -> Vec<(PathBuf, Option<PathBuf>)> {
+        let mut trash_dirs = vec![(self.trash_dir.clone(), None)];
+
+        #[cfg(target_os = "linux")]
+        for (trash_dir, topdir) in mount_trashes() {
+            if trash_dir != self.trash_dir {
+                trash_dirs.push((trash_dir, Some(topdir)));
+            }
+        }
+
+        trash_dirs
+    }
+}
+
+fn create_trash_dirs(trash_dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        // Trashed files keep their names, other users shouldn't be able to look around
+        builder.mode(0o700);
+    }
+
+    builder.create(trash_dir.join("files"))?;
+    builder.create(trash_dir.join("info"))
+}
+
+// Claims a name in the trash by creating its .trashinfo file, which fails if another program
+// got there first. Clashing names get a number appended
+fn reserve_name(trash_dir: &Path, path: &Path) -> io::Result<(OsString, PathBuf, fs::File)> {
+    let base = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Can't trash {}", path.display()),
+        )
+    })?;
+
+    let mut counter = 1;
+    loop {
+        let mut name = base.to_os_string();
+        if counter > 1 {
+            name.push(format!(".{}", counter));
+        }
+        counter += 1;
+
+        if fs::symlink_metadata(trash_dir.join("files").join(&name)).is_ok() {
+            continue;
+        }
+
+        let info_path = info_path_in(trash_dir, &name);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => return Ok((name, info_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn info_path_in(trash_dir: &Path, name: &std::ffi::OsStr) -> PathBuf {
+    let mut info_name = name.to_os_string();
+    info_name.push(format!(".{}", INFO_EXTENSION));
+    trash_dir.join("info").join(info_name)
+}
+
+// files/<name> has its info in ../info/<name>.trashinfo
+fn info_path_for(trash_path: &Path) -> Option<PathBuf> {
+    let trash_dir = trash_path.parent()?.parent()?;
+    Some(info_path_in(trash_dir, trash_path.file_name()?))
+}
+
+fn read_trash_dir(trash_dir: &Path, topdir: Option<&Path>) -> Vec<TrashItem> {
+    let Ok(read_dir) = fs::read_dir(trash_dir.join("info")) else {
+        return Vec::new();
+    };
+
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let info_path = entry.path();
+            if info_path.extension()? != INFO_EXTENSION {
+                return None;
+            }
+
+            let trash_path = trash_dir.join("files").join(info_path.file_stem()?);
+            fs::symlink_metadata(&trash_path).ok()?;
+
+            let content = fs::read_to_string(&info_path).ok()?;
+            let (path, deleted_at) = parse_info(&content)?;
+            let original_path = match topdir {
+                Some(top) if path.is_relative() => top.join(path),
+                _ => path,
+            };
+
+            Some(TrashItem {
+                size: Arc::new(Mutex::new(None)),
+                trash_path,
+                original_path,
+                deleted_at,
+            })
+        })
+        .collect()
+}
+
+fn format_info(path: &Path, deleted_at: NaiveDateTime) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(path),
+        deleted_at.format(DATE_FORMAT)
+    )
+}
+
+fn parse_info(content: &str) -> Option<(PathBuf, Option<NaiveDateTime>)> {
+    let mut lines = content.lines().skip_while(|l| l.trim() != "[Trash Info]");
+    lines.next()?;
+
+    let mut path = None;
+    let mut deleted_at = None;
+    for line in lines {
+        if line.starts_with('[') {
+            break;
+        }
+        match line.split_once('=') {
+            Some(("Path", value)) => path = Some(decode_path(value.trim())),
+            Some(("DeletionDate", value)) => {
+                deleted_at = NaiveDateTime::parse_from_str(value.trim(), DATE_FORMAT).ok()
+            }
+            _ => {}
+        }
+    }
+
+    Some((path?, deleted_at))
+}
+
+// The spec stores paths URL escaped, everything but unreserved characters and slashes
+fn encode_path(path: &Path) -> String {
+    let bytes = path_bytes(path);
+    let mut encoded = String::with_capacity(bytes.len());
+    for byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    path_from_bytes(decoded)
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// The highest directory above path that's still on the same device
+#[cfg(target_os = "linux")]
+fn mount_topdir(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = fs::symlink_metadata(path).ok()?.dev();
+    let mut topdir = path;
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(m) if m.dev() == dev => topdir = ancestor,
+            _ => break,
+        }
+    }
+    Some(topdir.to_path_buf())
+}
+
+// An admin provided $topdir/.Trash/$uid when $topdir/.Trash is a real sticky directory,
+// then our own $topdir/.Trash-$uid. Neither needs to exist yet
+#[cfg(target_os = "linux")]
+fn topdir_trashes(topdir: &Path) -> Vec<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let uid = unsafe { libc::getuid() };
+    let mut trashes = Vec::new();
+    let shared = topdir.join(".Trash");
+    if let Ok(m) = fs::symlink_metadata(&shared)
+        && m.is_dir()
+        && m.permissions().mode() & 0o1000 != 0
+    {
+        trashes.push(shared.join(uid.to_string()));
+    }
+    trashes.push(topdir.join(format!(".Trash-{}", uid)));
+    trashes
+}
+
+// The per mount trashes that already exist, paired with their mount's top directory
+#[cfg(target_os = "linux")]
+fn mount_trashes() -> Vec<(PathBuf, PathBuf)> {
+    let uid = unsafe { libc::getuid() };
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+
+    let mut trashes = Vec::new();
+    for line in mounts.lines() {
+        let Some(mount_point) = line.split_whitespace().nth(1) else {
+            continue;
+        };
+        let topdir = PathBuf::from(unescape_mount_point(mount_point));
+        for trash_dir in [
+            topdir.join(".Trash").join(uid.to_string()),
+            topdir.join(format!(".Trash-{}", uid)),
+        ] {
+            if trash_dir.join("info").is_dir() && !trashes.iter().any(|(t, _)| *t == trash_dir) {
+                trashes.push((trash_dir, topdir.clone()));
+            }
+        }
+    }
+    trashes
+}
+
+// /proc/self/mounts writes spaces and a few other characters as octal escapes like \040
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.clone().take(3).collect();
+            if let Ok(byte) = u8::from_str_radix(&code, 8) {
+                unescaped.push(byte as char);
+                chars.nth(2);
+                continue;
+            }
+        }
+        unescaped.push(c);
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_path, encode_path, format_info, parse_info};
+    use chrono::NaiveDate;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn paths_are_url_escaped() {
+        assert_eq!(
+            encode_path(Path::new("/home/me/a b.txt")),
+            "/home/me/a%20b.txt"
+        );
+        assert_eq!(encode_path(Path::new("50%#1")), "50%25%231");
+        assert_eq!(encode_path(Path::new("é")), "%C3%A9");
+        assert_eq!(encode_path(Path::new("keep-_.~/")), "keep-_.~/");
+    }
+
+    #[test]
+    fn decoding_reverses_encoding() {
+        for path in ["/home/me/a b.txt", "50%#1", "/tmp/é/[x]", "dir/.hidden"] {
+            assert_eq!(
+                decode_path(&encode_path(Path::new(path))),
+                PathBuf::from(path)
+            );
+        }
+    }
+
+    #[test]
+    fn stray_percent_signs_are_kept() {
+        assert_eq!(decode_path("100%"), PathBuf::from("100%"));
+        assert_eq!(decode_path("%zz%2"), PathBuf::from("%zz%2"));
+    }
+
+    #[test]
+    fn info_files_round_trip() {
+        let deleted_at = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(14, 5, 30)
+            .unwrap();
+        let info = format_info(Path::new("/home/me/old notes.txt"), deleted_at);
+        assert_eq!(
+            info,
+            "[Trash Info]\nPath=/home/me/old%20notes.txt\nDeletionDate=2024-03-09T14:05:30\n"
+        );
+        assert_eq!(
+            parse_info(&info),
+            Some((PathBuf::from("/home/me/old notes.txt"), Some(deleted_at)))
+        );
+    }
+
+    #[test]
+    fn info_files_from_other_programs() {
+        let info = "# written by hand\n[Trash Info]\nDeletionDate=not a date\nPath=rel/a%2Fb\n[Other]\nPath=/ignored\n";
+        assert_eq!(parse_info(info), Some((PathBuf::from("rel/a/b"), None)));
+        assert_eq!(
+            parse_info("[Trash Info]\nDeletionDate=2024-03-09T14:05:30\n"),
+            None
+        );
+        assert_eq!(parse_info("Path=/no/header\n"), None);
+    }
 }
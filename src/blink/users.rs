@@ -0,0 +1,100 @@
+// Looks up user and group names through the system's passwd and group databases.
+// Uses the reentrant libc calls since previews and jobs run on other threads
+#[cfg(unix)]
+mod imp {
+    use std::ffi::{CStr, CString};
+    use std::{mem, ptr};
+
+    // Runs a *_r lookup, growing the buffer while libc says it's too small. The entry points
+    // into the buffer, so whatever is needed gets pulled out before the buffer goes away
+    fn lookup<T, R>(
+        call: impl Fn(*mut T, *mut libc::c_char, usize, *mut *mut T) -> libc::c_int,
+        extract: impl Fn(&T) -> R,
+    ) -> Option<R> {
+        let mut size = 1024;
+        while size <= 1024 * 1024 {
+            let mut entry: T = unsafe { mem::zeroed() };
+            let mut buffer = vec![0 as libc::c_char; size];
+            let mut result = ptr::null_mut();
+
+            match call(&mut entry, buffer.as_mut_ptr(), size, &mut result) {
+                libc::ERANGE => size *= 2,
+                0 if !result.is_null() => return Some(extract(&entry)),
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    pub fn user_name(uid: u32) -> Option<String> {
+        lookup(
+            |pwd, buf, len, result| unsafe { libc::getpwuid_r(uid, pwd, buf, len, result) },
+            |pwd: &libc::passwd| {
+                unsafe { CStr::from_ptr(pwd.pw_name) }
+                    .to_string_lossy()
+                    .into_owned()
+            },
+        )
+    }
+
+    pub fn group_name(gid: u32) -> Option<String> {
+        lookup(
+            |grp, buf, len, result| unsafe { libc::getgrgid_r(gid, grp, buf, len, result) },
+            |grp: &libc::group| {
+                unsafe { CStr::from_ptr(grp.gr_name) }
+                    .to_string_lossy()
+                    .into_owned()
+            },
+        )
+    }
+
+    pub fn user_id(name: &str) -> Option<u32> {
+        let name = CString::new(name).ok()?;
+        lookup(
+            |pwd, buf, len, result| unsafe {
+                libc::getpwnam_r(name.as_ptr(), pwd, buf, len, result)
+            },
+            |pwd: &libc::passwd| pwd.pw_uid,
+        )
+    }
+
+    pub fn group_id(name: &str) -> Option<u32> {
+        let name = CString::new(name).ok()?;
+        lookup(
+            |grp, buf, len, result| unsafe {
+                libc::getgrnam_r(name.as_ptr(), grp, buf, len, result)
+            },
+            |grp: &libc::group| grp.gr_gid,
+        )
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    pub fn user_name(_uid: u32) -> Option<String> {
+        None
+    }
+
+    pub fn group_name(_gid: u32) -> Option<String> {
+        None
+    }
+
+    pub fn user_id(_name: &str) -> Option<u32> {
+        None
+    }
+
+    pub fn group_id(_name: &str) -> Option<u32> {
+        None
+    }
+}
+
+pub use imp::{group_id, group_name, user_id, user_name};
+
+// Accepts a name or a plain numeric id
+pub fn parse_user(input: &str) -> Option<u32> {
+    input.parse().ok().or_else(|| user_id(input))
+}
+
+pub fn parse_group(input: &str) -> Option<u32> {
+    input.parse().ok().or_else(|| group_id(input))
+}
@@ -0,0 +1,211 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+// How long the watched directories have to stay quiet before a refresh, and how long a steady
+// stream of changes (a build writing files) can hold one off
+const QUIET_PERIOD: Duration = Duration::from_millis(250);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+// Watches the current and parent directory for changes made outside blink. Changes are
+// collected until things calm down so a burst of them only causes one refresh
+pub struct Watcher {
+    backend: Backend,
+    changed: Vec<PathBuf>,
+    first_change: Option<Instant>,
+    last_change: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new() -> Self {
+        Self {
+            backend: Backend::new(),
+            changed: Vec::new(),
+            first_change: None,
+            last_change: None,
+        }
+    }
+
+    // Replaces the watched directories. Changes collected for the old ones are dropped since
+    // the listing is rebuilt anyway
+    pub fn watch(&mut self, dirs: &[&Path]) {
+        self.backend.watch(dirs);
+        self.changed.clear();
+        self.first_change = None;
+        self.last_change = None;
+    }
+
+    // The paths that changed once they've settled, a watched directory itself is in there when
+    // it's unknown what inside it changed
+    pub fn poll(&mut self) -> Option<Vec<PathBuf>> {
+        let changed = self.backend.read_changes();
+        let now = Instant::now();
+        if !changed.is_empty() {
+            self.changed.extend(changed);
+            self.first_change.get_or_insert(now);
+            self.last_change = Some(now);
+        }
+
+        let first = self.first_change?;
+        let last = self.last_change?;
+        if now - last < QUIET_PERIOD && now - first < MAX_DELAY {
+            return None;
+        }
+        self.first_change = None;
+        self.last_change = None;
+        Some(std::mem::take(&mut self.changed))
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Backend {
+    fd: Option<std::os::fd::OwnedFd>,
+    // Watch descriptors and the directories they stand for
+    watches: Vec<(libc::c_int, PathBuf)>,
+}
+
+#[cfg(target_os = "linux")]
+impl Backend {
+    fn new() -> Self {
+        use std::os::fd::FromRawFd;
+
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        Self {
+            // Without inotify nothing refreshes on its own, everything else keeps working
+            fd: (fd >= 0).then(|| unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) }),
+            watches: Vec::new(),
+        }
+    }
+
+    fn watch(&mut self, dirs: &[&Path]) {
+        use std::os::{fd::AsRawFd, unix::ffi::OsStrExt};
+
+        let Some(fd) = &self.fd else {
+            return;
+        };
+        for (wd, _) in self.watches.drain(..) {
+            unsafe { libc::inotify_rm_watch(fd.as_raw_fd(), wd) };
+        }
+
+        let mask = libc::IN_CREATE
+            | libc::IN_DELETE
+            | libc::IN_MODIFY
+            | libc::IN_ATTRIB
+            | libc::IN_CLOSE_WRITE
+            | libc::IN_MOVED_FROM
+            | libc::IN_MOVED_TO
+            | libc::IN_DELETE_SELF
+            | libc::IN_MOVE_SELF
+            | libc::IN_ONLYDIR;
+        for dir in dirs {
+            let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+                continue;
+            };
+            let wd = unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), mask) };
+            // The cwd and its parent are the same watch at the root
+            if wd >= 0 && !self.watches.iter().any(|(known, _)| *known == wd) {
+                self.watches.push((wd, dir.to_path_buf()));
+            }
+        }
+    }
+
+    fn read_changes(&mut self) -> Vec<PathBuf> {
+        use std::os::{fd::AsRawFd, unix::ffi::OsStrExt};
+
+        let Some(fd) = &self.fd else {
+            return Vec::new();
+        };
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut changed = Vec::new();
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            let read = unsafe {
+                libc::read(
+                    fd.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                )
+            };
+            if read <= 0 {
+                return changed;
+            }
+
+            let mut offset = 0;
+            while offset + header <= read as usize {
+                let event: libc::inotify_event = unsafe {
+                    std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const libc::inotify_event)
+                };
+                let name_start = offset + header;
+                let name_end = (name_start + event.len as usize).min(read as usize);
+                offset = name_start + event.len as usize;
+
+                // Events were dropped, so anything in the watched directories could have changed
+                if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                    changed.extend(self.watches.iter().map(|(_, dir)| dir.clone()));
+                    continue;
+                }
+                let Some((_, dir)) = self.watches.iter().find(|(wd, _)| *wd == event.wd) else {
+                    continue;
+                };
+                // The name is padded with nul bytes, an empty one is the directory itself
+                let name = &buffer[name_start..name_end];
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                if name.is_empty() {
+                    changed.push(dir.clone());
+                } else {
+                    changed.push(dir.join(std::ffi::OsStr::from_bytes(name)));
+                }
+            }
+        }
+    }
+}
+
+// Elsewhere the watched directories are checked for a new mtime every so often, which tells
+// that something changed but not what
+#[cfg(not(target_os = "linux"))]
+struct Backend {
+    dirs: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+    last_check: Instant,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Backend {
+    const INTERVAL: Duration = Duration::from_millis(500);
+
+    fn new() -> Self {
+        Self {
+            dirs: Vec::new(),
+            last_check: Instant::now(),
+        }
+    }
+
+    fn modified(dir: &Path) -> Option<std::time::SystemTime> {
+        std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+    }
+
+    fn watch(&mut self, dirs: &[&Path]) {
+        self.dirs = dirs
+            .iter()
+            .map(|dir| (dir.to_path_buf(), Self::modified(dir)))
+            .collect();
+    }
+
+    fn read_changes(&mut self) -> Vec<PathBuf> {
+        if self.last_check.elapsed() < Self::INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+
+        let mut changed = Vec::new();
+        for (dir, modified) in self.dirs.iter_mut() {
+            let now = Self::modified(dir);
+            if now != *modified {
+                *modified = now;
+                changed.push(dir.clone());
+            }
+        }
+        changed
+    }
+}